@@ -0,0 +1,78 @@
+//! Attributes for running tests against the test worker in a local Miniflare instance.
+
+use proc_macro::TokenStream;
+use quote::quote;
+use syn::{parse_macro_input, spanned::Spanned, Error, Expr, FnArg, ItemFn};
+
+/// Turns an async function taking a `&TestFixture` into a tokio test that starts Miniflare for
+/// it, and kills it again once the test is done.
+///
+/// The `TestFixture` in scope is started with `TestFixture::start_with`, seeded with the key value
+/// pairs given as `seed`:
+///
+/// ```ignore
+/// #[miniflare_test(seed = [("key", "value")])]
+/// async fn get(fixture: &TestFixture) {
+///     fixture.assert_success("get").await;
+/// }
+/// ```
+///
+/// The test worker runs in Miniflare's JavaScript runtime, so tests drive it through the
+/// endpoints of the fixture rather than holding a `KvStore` themselves.
+#[proc_macro_attribute]
+pub fn miniflare_test(args: TokenStream, item: TokenStream) -> TokenStream {
+    let mut seed = None;
+    let parser = syn::meta::parser(|meta| {
+        if meta.path.is_ident("seed") {
+            seed = Some(meta.value()?.parse::<Expr>()?);
+            Ok(())
+        } else {
+            Err(meta.error("expected `seed = [(key, value), ..]`"))
+        }
+    });
+    parse_macro_input!(args with parser);
+
+    let function = parse_macro_input!(item as ItemFn);
+    expand(function, seed)
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+fn expand(function: ItemFn, seed: Option<Expr>) -> Result<proc_macro2::TokenStream, Error> {
+    let ItemFn {
+        attrs,
+        vis,
+        mut sig,
+        block,
+    } = function;
+
+    if sig.asyncness.is_none() {
+        return Err(Error::new(sig.fn_token.span(), "miniflare tests must be async"));
+    }
+    if sig.inputs.len() != 1 {
+        return Err(Error::new(
+            sig.inputs.span(),
+            "miniflare tests take a single `&TestFixture` argument",
+        ));
+    }
+    let (pat, ty) = match sig.inputs.pop().map(|pair| pair.into_value()) {
+        Some(FnArg::Typed(arg)) => (arg.pat, arg.ty),
+        Some(arg) => return Err(Error::new(arg.span(), "expected a `&TestFixture` argument")),
+        None => unreachable!("the number of arguments was checked"),
+    };
+    let seed = match seed {
+        Some(seed) => quote!(&#seed),
+        None => quote!(&[]),
+    };
+
+    Ok(quote! {
+        #(#attrs)*
+        #[::tokio::test]
+        #vis #sig {
+            let fixture = TestFixture::start_with(#seed)
+                .expect("unable to spawn miniflare, did you install node modules?");
+            let #pat: #ty = &fixture;
+            #block
+        }
+    })
+}
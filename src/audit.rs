@@ -0,0 +1,74 @@
+use futures_util::future::try_join_all;
+use js_sys::Date;
+use serde::{Deserialize, Serialize};
+
+use crate::{JsonValue, KvError, KvStore};
+
+/// A record of a single change made through [`KvStore::audit_log_put`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AuditEntry {
+    /// When the change was made, in milliseconds since the unix epoch.
+    pub timestamp_ms: u64,
+    /// Who made the change.
+    pub actor: String,
+    /// The value stored before the change, if there was one.
+    pub old_value: Option<String>,
+    /// The value stored by the change, as JSON.
+    pub new_value: String,
+}
+
+impl KvStore {
+    /// Puts the value as JSON under `name` and records the change, along with the previous value
+    /// and the actor making it, under an `__audit__{name}__{timestamp_ms}` key.
+    ///
+    /// The audit record is written after the value, so a failure can leave a change without a
+    /// record but never a record without a change.
+    pub async fn audit_log_put<T: Serialize>(
+        &self,
+        name: &str,
+        value: &T,
+        actor: &str,
+    ) -> Result<(), KvError> {
+        let old_value = self.get(name).text().await?;
+        let new_value = serde_json::to_string(value)?;
+        self.put(name, new_value.as_str())?.execute().await?;
+
+        let entry = AuditEntry {
+            timestamp_ms: Date::now() as u64,
+            actor: actor.to_owned(),
+            old_value,
+            new_value,
+        };
+        let audit_key = format!("{}{:013}", audit_prefix(name), entry.timestamp_ms);
+        self.put(&audit_key, JsonValue(&entry))?.execute().await?;
+
+        Ok(())
+    }
+
+    /// Gets every change recorded for `name` by [`KvStore::audit_log_put`], oldest first.
+    pub async fn get_audit_log(&self, name: &str) -> Result<Vec<AuditEntry>, KvError> {
+        let prefix = audit_prefix(name);
+
+        // Other keys can share the prefix, e.g. `a__b` for `a`, so only keep the keys that end in
+        // a timestamp.
+        let keys: Vec<String> = self
+            .list_keys(Some(&prefix))
+            .await?
+            .into_iter()
+            .map(|key| key.name)
+            .filter(|key| key[prefix.len()..].parse::<u64>().is_ok())
+            .collect();
+
+        let entries =
+            try_join_all(keys.iter().map(|key| self.get(key).json::<AuditEntry>())).await?;
+        let mut entries: Vec<AuditEntry> = entries.into_iter().flatten().collect();
+        entries.sort_by_key(|entry| entry.timestamp_ms);
+
+        Ok(entries)
+    }
+}
+
+/// The prefix the audit records for `name` are stored under.
+fn audit_prefix(name: &str) -> String {
+    format!("__audit__{}__", name)
+}
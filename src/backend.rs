@@ -0,0 +1,58 @@
+use serde::de::DeserializeOwned;
+
+use crate::{KvError, KvStore, KvValue, ListOptions, ListResponse, PutOptions};
+
+/// The basic operations of a kv store, so code can be written against either a [`KvStore`] or an
+/// in-memory stand-in such as the `FakeKvStore` of the `testing` module.
+///
+/// The futures returned by the methods are not `Send`, which is fine on the single-threaded
+/// workers runtime.
+#[allow(async_fn_in_trait)]
+pub trait KvBackend {
+    /// Gets the raw value stored under `name`.
+    async fn get(&self, name: &str) -> Result<Option<KvValue>, KvError>;
+
+    /// Gets the raw value stored under `name` along with its metadata.
+    async fn get_with_metadata<M: DeserializeOwned>(
+        &self,
+        name: &str,
+    ) -> Result<(Option<KvValue>, Option<M>), KvError>;
+
+    /// Puts the bytes under `name` with the given expiration and metadata.
+    async fn put(&self, name: &str, value: &[u8], options: PutOptions) -> Result<(), KvError>;
+
+    /// Lists the keys matching the options.
+    async fn list(&self, options: ListOptions) -> Result<ListResponse, KvError>;
+
+    /// Deletes the value stored under `name`.
+    async fn delete(&self, name: &str) -> Result<(), KvError>;
+}
+
+impl KvBackend for KvStore {
+    async fn get(&self, name: &str) -> Result<Option<KvValue>, KvError> {
+        KvStore::get(self, name).value().await
+    }
+
+    async fn get_with_metadata<M: DeserializeOwned>(
+        &self,
+        name: &str,
+    ) -> Result<(Option<KvValue>, Option<M>), KvError> {
+        let (value, metadata) = KvStore::get(self, name).bytes_with_metadata().await?;
+        Ok((value.map(KvValue), metadata))
+    }
+
+    async fn put(&self, name: &str, value: &[u8], options: PutOptions) -> Result<(), KvError> {
+        self.put_bytes(name, value)?
+            .from_options(options)
+            .execute()
+            .await
+    }
+
+    async fn list(&self, options: ListOptions) -> Result<ListResponse, KvError> {
+        KvStore::list(self).from_options(options).execute().await
+    }
+
+    async fn delete(&self, name: &str) -> Result<(), KvError> {
+        KvStore::delete(self, name).await
+    }
+}
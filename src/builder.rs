@@ -1,33 +1,78 @@
-use js_sys::{ArrayBuffer, Function, Object, Promise, Uint8Array};
-use serde::{de::DeserializeOwned, Serialize};
-use serde_json::Value;
+#[cfg(feature = "tracing")]
+use std::sync::Arc;
+
+use js_sys::{ArrayBuffer, Function, Math, Object, Promise, Uint8Array, JSON};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::{Map, Value};
 use wasm_bindgen::{JsCast, JsValue};
 use wasm_bindgen_futures::JsFuture;
+use web_sys::{ReadableStream, Response};
 
-use crate::{KvError, ListResponse};
+use crate::{KvError, KvValue, ListResponse};
 
 /// A builder to configure put requests.
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone)]
 #[must_use = "PutOptionsBuilder does nothing until you 'execute' it"]
 pub struct PutOptionsBuilder {
-    #[serde(skip)]
     pub(crate) this: Object,
-    #[serde(skip)]
     pub(crate) put_function: Function,
-    #[serde(skip)]
     pub(crate) name: JsValue,
-    #[serde(skip)]
     pub(crate) value: JsValue,
-    #[serde(skip_serializing_if = "Option::is_none")]
     pub(crate) expiration: Option<u64>,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    #[serde(rename = "expirationTtl")]
     pub(crate) expiration_ttl: Option<u64>,
-    #[serde(skip_serializing_if = "Option::is_none")]
     pub(crate) metadata: Option<Value>,
+    /// Fields used by the crate itself, merged into the user provided metadata on execute.
+    pub(crate) reserved_metadata: Map<String, Value>,
+    #[cfg(feature = "tracing")]
+    pub(crate) binding: Arc<str>,
+}
+
+/// # Safety
+///
+/// Like [`KvStore`](crate::KvStore), a builder only holds JavaScript handles that are never used
+/// off the single thread of the Wasm executor, see the `Send` impl of `KvStore`.
+#[cfg(all(target_arch = "wasm32", not(target_feature = "atomics")))]
+unsafe impl Send for PutOptionsBuilder {}
+
+/// # Safety
+///
+/// See the `Send` impl above.
+#[cfg(all(target_arch = "wasm32", not(target_feature = "atomics")))]
+unsafe impl Sync for PutOptionsBuilder {}
+
+/// The options of a put request as a plain value, e.g. to be loaded from configuration and applied
+/// with [`PutOptionsBuilder::from_options`].
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PutOptions {
+    /// When (expressed as a [unix timestamp](https://en.wikipedia.org/wiki/Unix_time)) the key
+    /// value pair will expire in the store.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub expiration: Option<u64>,
+    /// How many seconds until the key value pair will expire.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub expiration_ttl: Option<u64>,
+    /// Metadata to be stored with the key value pair.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub metadata: Option<Value>,
 }
 
 impl PutOptionsBuilder {
+    /// Replaces all of the options of the builder with the given ones.
+    pub fn from_options(mut self, options: PutOptions) -> Self {
+        self.expiration = options.expiration;
+        self.expiration_ttl = options.expiration_ttl;
+        self.metadata = options.metadata;
+        self
+    }
+    /// The options currently set on the builder.
+    pub fn build(&self) -> PutOptions {
+        PutOptions {
+            expiration: self.expiration,
+            expiration_ttl: self.expiration_ttl,
+            metadata: self.metadata.clone(),
+        }
+    }
     /// When (expressed as a [unix timestamp](https://en.wikipedia.org/wiki/Unix_time)) the key
     /// value pair will expire in the store.
     pub fn expiration(mut self, expiration: u64) -> Self {
@@ -39,6 +84,17 @@ impl PutOptionsBuilder {
         self.expiration_ttl = Some(expiration_ttl);
         self
     }
+    /// Sets a random expiration ttl between `base_ttl_secs` and `base_ttl_secs + jitter_secs`
+    /// seconds, both inclusive, so keys cached at the same time by many workers don't all expire
+    /// at the same moment.
+    ///
+    /// The ttl is `base_ttl_secs + floor(Math.random() * (jitter_secs + 1))`, which is uniformly
+    /// distributed, so keys live for `base_ttl_secs + jitter_secs / 2` seconds on average.
+    pub fn with_expiration_jitter(mut self, base_ttl_secs: u64, jitter_secs: u64) -> Self {
+        let jitter = (Math::random() * (jitter_secs as f64 + 1.0)) as u64;
+        self.expiration_ttl = Some(base_ttl_secs.saturating_add(jitter.min(jitter_secs)));
+        self
+    }
     /// Metadata to be stored with the key value pair.
     pub fn metadata<T: Serialize>(mut self, metadata: T) -> Result<Self, KvError> {
         self.metadata = Some(serde_json::to_value(metadata)?);
@@ -46,15 +102,73 @@ impl PutOptionsBuilder {
     }
     /// Puts the value in the kv store.
     pub async fn execute(self) -> Result<(), KvError> {
-        let options_object = serde_wasm_bindgen::to_value(&self).map_err(JsValue::from)?;
-        let promise: Promise = self
-            .put_function
-            .call3(&self.this, &self.name, &self.value, &options_object)?
-            .into();
-        JsFuture::from(promise)
-            .await
-            .map(|_| ())
-            .map_err(KvError::from)
+        instrumented!(
+            "put",
+            self.binding.clone(),
+            self.name.as_string(),
+            |_| None,
+            async move {
+                crate::check_value_size(&self.value)?;
+
+                // The options are set field by field so that the metadata only goes through a
+                // single JSON round-trip, rather than being serialized again as part of the whole
+                // builder.
+                let options_object = Object::new();
+                if let Some(expiration) = self.expiration {
+                    crate::set(&options_object, "expiration", &(expiration as f64).into())?;
+                }
+                if let Some(expiration_ttl) = self.expiration_ttl {
+                    crate::set(
+                        &options_object,
+                        "expirationTtl",
+                        &(expiration_ttl as f64).into(),
+                    )?;
+                }
+                if let Some(metadata) = &self.merged_metadata()? {
+                    let metadata = serde_json::to_string(metadata)?;
+                    if metadata.len() > crate::MAX_METADATA_SIZE {
+                        return Err(KvError::MetadataTooLarge {
+                            actual_bytes: metadata.len(),
+                        });
+                    }
+                    crate::set(&options_object, "metadata", &JSON::parse(&metadata)?)?;
+                }
+
+                let promise: Promise = self
+                    .put_function
+                    .call3(&self.this, &self.name, &self.value, &options_object)?
+                    .into();
+                JsFuture::from(promise)
+                    .await
+                    .map(|_| ())
+                    .map_err(KvError::from)
+            }
+        )
+    }
+
+    /// Sets a metadata field used by the crate itself, which is stored alongside the metadata set
+    /// with [`PutOptionsBuilder::metadata`].
+    pub(crate) fn reserved_metadata(mut self, name: &str, value: Value) -> Self {
+        self.reserved_metadata.insert(name.into(), value);
+        self
+    }
+
+    fn merged_metadata(&self) -> Result<Option<Value>, KvError> {
+        if self.reserved_metadata.is_empty() {
+            return Ok(self.metadata.clone());
+        }
+
+        let mut metadata = match &self.metadata {
+            Some(Value::Object(metadata)) => metadata.clone(),
+            Some(_) => {
+                return Err(KvError::InvalidMetadata(
+                    "metadata must be an object to be stored alongside reserved fields".into(),
+                ))
+            }
+            None => Map::new(),
+        };
+        metadata.extend(self.reserved_metadata.clone());
+        Ok(Some(Value::Object(metadata)))
     }
 }
 
@@ -72,9 +186,58 @@ pub struct ListOptionsBuilder {
     pub(crate) cursor: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub(crate) prefix: Option<String>,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub(crate) metadata: bool,
+    #[cfg(feature = "tracing")]
+    #[serde(skip)]
+    pub(crate) binding: Arc<str>,
+}
+
+/// # Safety
+///
+/// Like [`KvStore`](crate::KvStore), a builder only holds JavaScript handles that are never used
+/// off the single thread of the Wasm executor, see the `Send` impl of `KvStore`.
+#[cfg(all(target_arch = "wasm32", not(target_feature = "atomics")))]
+unsafe impl Send for ListOptionsBuilder {}
+
+/// # Safety
+///
+/// See the `Send` impl above.
+#[cfg(all(target_arch = "wasm32", not(target_feature = "atomics")))]
+unsafe impl Sync for ListOptionsBuilder {}
+
+/// The options of a list request as a plain value, e.g. to be loaded from configuration and
+/// applied with [`ListOptionsBuilder::from_options`].
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ListOptions {
+    /// The maximum number of keys returned.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub limit: Option<u64>,
+    /// A string returned by a previous response used to paginate the keys in the store.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cursor: Option<String>,
+    /// A prefix that all keys must start with for them to be included in the response.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub prefix: Option<String>,
 }
 
 impl ListOptionsBuilder {
+    /// Replaces all of the options of the builder with the given ones.
+    pub fn from_options(mut self, options: ListOptions) -> Self {
+        self.limit = options.limit;
+        self.cursor = options.cursor;
+        self.prefix = options.prefix;
+        self
+    }
+    /// The options currently set on the builder.
+    pub fn build(&self) -> ListOptions {
+        ListOptions {
+            limit: self.limit,
+            cursor: self.cursor.clone(),
+            prefix: self.prefix.clone(),
+        }
+    }
     /// The maximum number of keys returned. The default is 1000, which is the maximum. It is
     /// unlikely that you will want to change this default, but it is included for completeness.
     pub fn limit(mut self, limit: u64) -> Self {
@@ -91,17 +254,31 @@ impl ListOptionsBuilder {
         self.prefix = Some(prefix);
         self
     }
+    /// Asks for the metadata of every key to be included in [`Key::metadata`](crate::Key), saving
+    /// a `get_with_metadata` call per key.
+    pub fn with_metadata(mut self) -> Self {
+        self.metadata = true;
+        self
+    }
     /// Lists the key value pairs in the kv store.
     pub async fn execute(self) -> Result<ListResponse, KvError> {
-        let options_object = serde_wasm_bindgen::to_value(&self).map_err(JsValue::from)?;
-        let promise: Promise = self
-            .list_function
-            .call1(&self.this, &options_object)?
-            .into();
+        instrumented!(
+            "list",
+            self.binding.clone(),
+            self.prefix.clone(),
+            |_| None,
+            async move {
+                let options_object = crate::to_js_value(&self)?;
+                let promise: Promise = self
+                    .list_function
+                    .call1(&self.this, &options_object)?
+                    .into();
 
-        let value = JsFuture::from(promise).await?;
-        let resp = serde_wasm_bindgen::from_value(value).map_err(JsValue::from)?;
-        Ok(resp)
+                let value = JsFuture::from(promise).await?;
+                let resp = serde_wasm_bindgen::from_value(value).map_err(JsValue::from)?;
+                Ok(resp)
+            }
+        )
     }
 }
 
@@ -118,12 +295,39 @@ pub struct GetOptionsBuilder {
     #[serde(skip)]
     pub(crate) name: JsValue,
     #[serde(rename = "cacheTtl", skip_serializing_if = "Option::is_none")]
-    pub(crate) cache_ttl: Option<u64>,
+    pub(crate) cache_ttl: Option<u32>,
     #[serde(rename = "type", skip_serializing_if = "Option::is_none")]
-    pub(crate) value_type: Option<GetValueType>,
+    pub(crate) value_type: Option<KvValueType>,
+    #[cfg(feature = "tracing")]
+    #[serde(skip)]
+    pub(crate) binding: Arc<str>,
+}
+
+/// The options of a get request as a plain value, e.g. to be loaded from configuration and
+/// applied with [`GetOptionsBuilder::from_options`].
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GetOptions {
+    /// How long in seconds the value is cached in the edge location it is read from, see
+    /// [`GetOptionsBuilder::cache_ttl`].
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cache_ttl: Option<u32>,
 }
 
 impl GetOptionsBuilder {
+    /// Replaces all of the options of the builder with the given ones.
+    pub fn from_options(mut self, options: GetOptions) -> Self {
+        self.cache_ttl = options.cache_ttl;
+        self
+    }
+
+    /// The options currently set on the builder.
+    pub fn build(&self) -> GetOptions {
+        GetOptions {
+            cache_ttl: self.cache_ttl,
+        }
+    }
+
     /// The cache_ttl parameter must be an integer that is greater than or equal to 60. It defines
     /// the length of time in seconds that a KV result is cached in the edge location that it is
     /// accessed from. This can be useful for reducing cold read latency on keys that are read
@@ -131,28 +335,42 @@ impl GetOptionsBuilder {
     /// write-rarely, but is not recommended if your data is updated often and you need to see
     /// updates shortly after they're written, because writes that happen from other edge locations
     /// won't be visible until the cached value expires.
+    ///
+    /// Values that don't fit in a `u32` are capped at `u32::MAX` seconds.
     pub fn cache_ttl(mut self, cache_ttl: u64) -> Self {
-        self.cache_ttl = Some(cache_ttl);
+        self.cache_ttl = Some(cache_ttl.min(u64::from(u32::MAX)) as u32);
         self
     }
 
-    fn value_type(mut self, value_type: GetValueType) -> Self {
+    /// The type the kv store should return the value as for [`GetOptionsBuilder::execute`], the
+    /// default is [`KvValueType::ArrayBuffer`]. The other getters such as
+    /// [`GetOptionsBuilder::text`] always use their own type.
+    pub fn type_hint(mut self, value_type: KvValueType) -> Self {
         self.value_type = Some(value_type);
         self
     }
 
-    async fn get(self) -> Result<JsValue, KvError> {
-        let options_object = serde_wasm_bindgen::to_value(&self).map_err(JsValue::from)?;
-        let promise: Promise = self
-            .get_function
-            .call2(&self.this, &self.name, &options_object)?
-            .into();
-        JsFuture::from(promise).await.map_err(KvError::from)
+    pub(crate) async fn get(self) -> Result<JsValue, KvError> {
+        instrumented!(
+            "get",
+            self.binding.clone(),
+            self.name.as_string(),
+            |value: &JsValue| Some(!value.is_null()),
+            async move {
+                crate::validate_key(&self.name.as_string().unwrap_or_default())?;
+                let options_object = crate::to_js_value(&self)?;
+                let promise: Promise = self
+                    .get_function
+                    .call2(&self.this, &self.name, &options_object)?
+                    .into();
+                JsFuture::from(promise).await.map_err(KvError::from)
+            }
+        )
     }
 
     /// Gets the value as a string.
     pub async fn text(self) -> Result<Option<String>, KvError> {
-        let value = self.value_type(GetValueType::Text).get().await?;
+        let value = self.type_hint(KvValueType::Text).get().await?;
         Ok(value.as_string())
     }
 
@@ -161,7 +379,7 @@ impl GetOptionsBuilder {
     where
         T: DeserializeOwned,
     {
-        let value = self.value_type(GetValueType::Json).get().await?;
+        let value = self.type_hint(KvValueType::Json).get().await?;
         Ok(if value.is_null() {
             None
         } else {
@@ -171,7 +389,7 @@ impl GetOptionsBuilder {
 
     /// Gets the value as a byte slice.
     pub async fn bytes(self) -> Result<Option<Vec<u8>>, KvError> {
-        let v = self.value_type(GetValueType::ArrayBuffer).get().await?;
+        let v = self.type_hint(KvValueType::ArrayBuffer).get().await?;
         if ArrayBuffer::instanceof(&v) {
             let buffer = ArrayBuffer::from(v);
             let buffer = Uint8Array::new(&buffer);
@@ -181,28 +399,72 @@ impl GetOptionsBuilder {
         }
     }
 
-    async fn get_with_metadata<M>(&self) -> Result<(JsValue, Option<M>), KvError>
+    /// Gets the raw value, which can later be interpreted as a string, JSON or bytes.
+    pub async fn value(self) -> Result<Option<KvValue>, KvError> {
+        Ok(self.bytes().await?.map(KvValue))
+    }
+
+    /// Gets the value as the type set with [`GetOptionsBuilder::type_hint`], converted to its raw
+    /// bytes. JSON values are converted back to their JSON text and streams are read to the end.
+    pub async fn execute(self) -> Result<Option<KvValue>, KvError> {
+        let value_type = self.value_type.unwrap_or(KvValueType::ArrayBuffer);
+        let value = self.type_hint(value_type).get().await?;
+        to_kv_value(value, value_type).await
+    }
+
+    /// Like [`GetOptionsBuilder::execute`], but also gets the metadata of the value. A value
+    /// stored without metadata has its metadata deserialized from `null`, so use an `Option` for
+    /// `M` if not every value has metadata.
+    pub async fn execute_with_metadata<M>(self) -> Result<Option<(KvValue, M)>, KvError>
     where
         M: DeserializeOwned,
     {
-        let options_object = serde_wasm_bindgen::to_value(&self).map_err(JsValue::from)?;
-        let promise: Promise = self
-            .get_with_meta_function
-            .call2(&self.this, &self.name, &options_object)?
-            .into();
+        let value_type = self.value_type.unwrap_or(KvValueType::ArrayBuffer);
+        let (value, metadata) = self.type_hint(value_type).get_with_metadata::<M>().await?;
 
-        let pair = JsFuture::from(promise).await?;
-        let metadata = crate::get(&pair, "metadata")?;
-        let value = crate::get(&pair, "value")?;
+        match to_kv_value(value, value_type).await? {
+            Some(value) => {
+                let metadata = match metadata {
+                    Some(metadata) => metadata,
+                    None => serde_json::from_value(Value::Null)?,
+                };
+                Ok(Some((value, metadata)))
+            }
+            None => Ok(None),
+        }
+    }
 
-        Ok((
-            value,
-            if metadata.is_null() {
-                None
-            } else {
-                Some(serde_wasm_bindgen::from_value(metadata).map_err(JsValue::from)?)
-            },
-        ))
+    pub(crate) async fn get_with_metadata<M>(&self) -> Result<(JsValue, Option<M>), KvError>
+    where
+        M: DeserializeOwned,
+    {
+        instrumented!(
+            "getWithMetadata",
+            self.binding.clone(),
+            self.name.as_string(),
+            |(value, _): &(JsValue, Option<M>)| Some(!value.is_null()),
+            async {
+                crate::validate_key(&self.name.as_string().unwrap_or_default())?;
+                let options_object = crate::to_js_value(&self)?;
+                let promise: Promise = self
+                    .get_with_meta_function
+                    .call2(&self.this, &self.name, &options_object)?
+                    .into();
+
+                let pair = JsFuture::from(promise).await?;
+                let metadata = crate::get(&pair, "metadata")?;
+                let value = crate::get(&pair, "value")?;
+
+                Ok((
+                    value,
+                    if metadata.is_null() {
+                        None
+                    } else {
+                        Some(serde_wasm_bindgen::from_value(metadata).map_err(JsValue::from)?)
+                    },
+                ))
+            }
+        )
     }
 
     /// Gets the value as a string and it's associated metadata.
@@ -211,7 +473,7 @@ impl GetOptionsBuilder {
         M: DeserializeOwned,
     {
         let (value, metadata) = self
-            .value_type(GetValueType::Text)
+            .type_hint(KvValueType::Text)
             .get_with_metadata()
             .await?;
         Ok((value.as_string(), metadata))
@@ -224,7 +486,7 @@ impl GetOptionsBuilder {
         M: DeserializeOwned,
     {
         let (value, metadata) = self
-            .value_type(GetValueType::Json)
+            .type_hint(KvValueType::Json)
             .get_with_metadata()
             .await?;
         Ok((
@@ -243,7 +505,7 @@ impl GetOptionsBuilder {
         M: DeserializeOwned,
     {
         let (value, metadata) = self
-            .value_type(GetValueType::ArrayBuffer)
+            .type_hint(KvValueType::ArrayBuffer)
             .get_with_metadata()
             .await?;
 
@@ -257,10 +519,38 @@ impl GetOptionsBuilder {
     }
 }
 
-#[derive(Debug, Clone, Serialize)]
+/// Converts a value returned by the kv store as `value_type` to its raw bytes.
+async fn to_kv_value(value: JsValue, value_type: KvValueType) -> Result<Option<KvValue>, KvError> {
+    if value.is_null() || value.is_undefined() {
+        return Ok(None);
+    }
+
+    let bytes = match value_type {
+        KvValueType::Text => value.as_string().unwrap_or_default().into_bytes(),
+        KvValueType::Json => String::from(JSON::stringify(&value)?).into_bytes(),
+        KvValueType::ArrayBuffer => Uint8Array::new(&value).to_vec(),
+        KvValueType::Stream => {
+            // Wrapping the stream in a response is the simplest way to read it to the end.
+            let stream: ReadableStream = value.unchecked_into();
+            let response = Response::new_with_opt_readable_stream(Some(&stream))?;
+            let buffer = JsFuture::from(response.array_buffer()?).await?;
+            Uint8Array::new(&buffer).to_vec()
+        }
+    };
+
+    Ok(Some(KvValue(bytes)))
+}
+
+/// The type the kv store returns a value as, see [`GetOptionsBuilder::type_hint`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
 #[serde(rename_all = "camelCase")]
-pub(crate) enum GetValueType {
+pub enum KvValueType {
+    /// The value as a string.
     Text,
-    ArrayBuffer,
+    /// The value parsed as JSON.
     Json,
+    /// The raw bytes of the value.
+    ArrayBuffer,
+    /// The value as a `ReadableStream`.
+    Stream,
 }
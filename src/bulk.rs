@@ -0,0 +1,648 @@
+use std::{collections::HashMap, future::Future};
+
+use futures_util::future::{join_all, try_join_all};
+use js_sys::Date;
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::Value;
+
+use crate::{
+    JsonValue, Key, KvError, KvStore, KvValue, ListResponse, PutOptions, PutOptionsBuilder,
+    ToRawKvValue, MAX_LIST_LIMIT,
+};
+
+/// How many operations are sent to the kv store at once, to stay within the subrequest limits.
+const BATCH_SIZE: usize = 100;
+
+/// How many puts are sent to the kv store at once by [`KvStore::put_many_from_iter`].
+const PUT_BATCH_SIZE: usize = 50;
+
+/// The outcome of a [`KvStore::get_batch_json`] or [`KvStore::multi_get_with_metadata`] call.
+#[derive(Debug)]
+pub struct BatchGetResult<T> {
+    /// The value of every key that could be fetched and deserialized, or `None` if it was not
+    /// found.
+    pub values: HashMap<String, Option<T>>,
+    /// The keys that could not be fetched or deserialized along with the reason.
+    pub errors: HashMap<String, KvError>,
+}
+
+/// The outcome of a [`KvStore::scan_values`] call.
+#[derive(Debug)]
+pub struct ScanResult<T> {
+    /// The name and deserialized value of every scanned key, in listing order.
+    pub values: Vec<(String, T)>,
+    /// The keys whose value could not be deserialized.
+    pub malformed: Vec<String>,
+}
+
+/// A key listed by [`KvStore::list_with_value_preview`] along with the start of its value.
+#[derive(Debug, Clone)]
+pub struct KvKeyWithPreview {
+    /// The listed key.
+    pub key: Key,
+    /// The first characters of the value, or `None` if the key was deleted before its value was
+    /// fetched.
+    pub preview: Option<String>,
+}
+
+/// A batch of puts with their own options, created by [`KvStore::multi_put`].
+#[must_use = "MultiPutBuilder does nothing until you 'execute' it"]
+pub struct MultiPutBuilder {
+    store: KvStore,
+    puts: Vec<(String, Result<PutOptionsBuilder, KvError>)>,
+}
+
+/// The outcome of a [`MultiPutBuilder::execute`] call.
+#[derive(Debug)]
+pub struct MultiPutResult {
+    /// The keys that were put, in the order they were added.
+    pub succeeded: Vec<String>,
+    /// The keys that could not be serialized or put along with the reason.
+    pub failed: Vec<(String, KvError)>,
+}
+
+impl MultiPutBuilder {
+    /// Adds putting the value as JSON under `name` with its own options to the batch.
+    ///
+    /// A value that fails to serialize is reported in [`MultiPutResult::failed`] rather than
+    /// failing the whole batch.
+    pub fn add<T: Serialize>(mut self, name: &str, value: &T, options: PutOptions) -> Self {
+        let builder = self
+            .store
+            .put(name, JsonValue(value))
+            .map(|builder| builder.from_options(options));
+        self.puts.push((name.to_owned(), builder));
+        self
+    }
+
+    /// Concurrently executes all of the puts, 50 at a time to stay within the subrequest limits.
+    pub async fn execute(self) -> MultiPutResult {
+        let mut result = MultiPutResult {
+            succeeded: Vec::new(),
+            failed: Vec::new(),
+        };
+        let mut puts = self.puts.into_iter();
+
+        loop {
+            let batch: Vec<_> = puts.by_ref().take(PUT_BATCH_SIZE).collect();
+            if batch.is_empty() {
+                return result;
+            }
+
+            let outcomes = join_all(batch.into_iter().map(|(name, builder)| async move {
+                let outcome = match builder {
+                    Ok(builder) => builder.execute().await,
+                    Err(e) => Err(e),
+                };
+                (name, outcome)
+            }))
+            .await;
+
+            for (name, outcome) in outcomes {
+                match outcome {
+                    Ok(()) => result.succeeded.push(name),
+                    Err(e) => result.failed.push((name, e)),
+                }
+            }
+        }
+    }
+}
+
+impl KvStore {
+    /// Deletes every key, optionally restricted to those starting with `prefix`, whose expiration
+    /// is in the past according to `Date.now()`. Returns the number of deleted keys.
+    ///
+    /// Cloudflare may already consider these keys expired and will eventually remove them on its
+    /// own, this only forces the cleanup for workers that need to stay within a storage budget.
+    pub async fn delete_expired(&self, prefix: Option<&str>) -> Result<u64, KvError> {
+        let now = (Date::now() / 1000.0) as u64;
+        let expired: Vec<String> = self
+            .list_keys(prefix)
+            .await?
+            .into_iter()
+            .filter(|key| matches!(key.expiration, Some(expiration) if expiration <= now))
+            .map(|key| key.name)
+            .collect();
+
+        self.delete_all(&expired).await
+    }
+
+    /// Deletes every key starting with `prefix`, listing them page by page until the listing is
+    /// complete, and returns the number of deleted keys.
+    ///
+    /// The keys are all listed before any is deleted, so keys added under the prefix while this
+    /// runs may survive.
+    pub async fn delete_prefix(&self, prefix: &str) -> Result<usize, KvError> {
+        let names: Vec<String> = self
+            .list_keys(Some(prefix))
+            .await?
+            .into_iter()
+            .map(|key| key.name)
+            .collect();
+
+        self.delete_all(&names).await?;
+        Ok(names.len())
+    }
+
+    /// Keeps only the keys, optionally restricted to those starting with `prefix`, for which
+    /// `predicate` returns `true` and deletes the rest. Returns the number of deleted keys.
+    ///
+    /// This is the kv equivalent of [`Vec::retain`], e.g. to evict cache entries whose metadata
+    /// timestamp is older than some threshold.
+    pub async fn retain<F: Fn(&Key) -> bool>(
+        &self,
+        prefix: Option<&str>,
+        predicate: F,
+    ) -> Result<u64, KvError> {
+        self.delete_where(prefix, |key| !predicate(key)).await
+    }
+
+    /// Concurrently deletes every key, optionally restricted to those starting with `prefix`, for
+    /// which `predicate` returns `true`. Returns the number of deleted keys.
+    ///
+    /// The predicate has access to the name, expiration and metadata of each key, e.g. to delete
+    /// every session belonging to a user.
+    pub async fn delete_where<F: Fn(&Key) -> bool>(
+        &self,
+        prefix: Option<&str>,
+        predicate: F,
+    ) -> Result<u64, KvError> {
+        let removed: Vec<String> = self
+            .list_keys(prefix)
+            .await?
+            .into_iter()
+            .filter(&predicate)
+            .map(|key| key.name)
+            .collect();
+
+        self.delete_all(&removed).await
+    }
+
+    /// Deletes all of the keys in a response of a previous [`KvStore::list`] call, returning the
+    /// number of deleted keys.
+    ///
+    /// ```ignore
+    /// let response = kv.list().prefix("tmp/".into()).execute().await?;
+    /// kv.delete_batch_from_list_response(response).await?;
+    /// ```
+    pub async fn delete_batch_from_list_response(
+        &self,
+        response: ListResponse,
+    ) -> Result<u64, KvError> {
+        let names: Vec<String> = response.keys.into_iter().map(|key| key.name).collect();
+        self.delete_all(&names).await
+    }
+
+    /// Renews the expiration of every key starting with `prefix` to `new_ttl_secs` seconds from
+    /// now, keeping their values and metadata. Returns the number of refreshed keys.
+    ///
+    /// Keys that expire between being listed and being refreshed are counted as refreshed, since
+    /// they are gone either way.
+    pub async fn refresh_all_expirations(
+        &self,
+        prefix: &str,
+        new_ttl_secs: u64,
+    ) -> Result<u64, KvError> {
+        let keys = self.list_keys(Some(prefix)).await?;
+
+        for batch in keys.chunks(BATCH_SIZE) {
+            try_join_all(batch.iter().map(|key| self.touch(&key.name, new_ttl_secs))).await?;
+        }
+
+        Ok(keys.len() as u64)
+    }
+
+    /// Deletes the keys starting with `prefix` that match `filter`, or all of them without a
+    /// filter, returning the number of deleted keys.
+    ///
+    /// Keys are listed `batch_size` at a time, capped at the 1000 keys a single list request can
+    /// return, and each page is deleted concurrently before the next one is fetched, so memory
+    /// usage stays bounded no matter how many keys there are.
+    pub async fn bulk_delete_from_prefix_scan(
+        &self,
+        prefix: &str,
+        filter: Option<impl Fn(&Key) -> bool>,
+        batch_size: usize,
+    ) -> Result<u64, KvError> {
+        let mut deleted = 0;
+        let mut cursor = None;
+
+        loop {
+            let mut builder = self
+                .list()
+                .prefix(prefix.into())
+                .limit((batch_size.max(1) as u64).min(MAX_LIST_LIMIT));
+            if let Some(cursor) = cursor {
+                builder = builder.cursor(cursor);
+            }
+
+            let page = builder.execute().await?;
+            let names: Vec<String> = page
+                .keys
+                .into_iter()
+                .filter(|key| filter.as_ref().is_none_or(|filter| filter(key)))
+                .map(|key| key.name)
+                .collect();
+            deleted += self.delete_all(&names).await?;
+
+            match page.cursor {
+                Some(next) if !page.list_complete => cursor = Some(next),
+                _ => return Ok(deleted),
+            }
+        }
+    }
+
+    /// Concurrently renews the expiration of all of the given keys to `new_ttl_secs` seconds from
+    /// now, the bulk form of [`KvStore::touch`]. Returns whether each key was refreshed, which is
+    /// `false` for missing keys, in the same order as `names`.
+    pub async fn ttl_extend_batch(
+        &self,
+        names: &[&str],
+        new_ttl_secs: u64,
+    ) -> Result<Vec<(String, bool)>, KvError> {
+        let mut refreshed = Vec::with_capacity(names.len());
+
+        for batch in names.chunks(BATCH_SIZE) {
+            let touched =
+                try_join_all(batch.iter().map(|name| self.touch(name, new_ttl_secs))).await?;
+            refreshed.extend(batch.iter().map(|name| name.to_string()).zip(touched));
+        }
+
+        Ok(refreshed)
+    }
+
+    /// Concurrently fetches and deserializes the values of all the given keys, mapping each name to
+    /// its value or `None` if the key was not found. Failing to deserialize any of the values fails
+    /// the whole call.
+    pub async fn get_multiple_json<T: DeserializeOwned>(
+        &self,
+        names: &[&str],
+    ) -> Result<HashMap<String, Option<T>>, KvError> {
+        let values = try_join_all(names.iter().map(|name| self.get(name).json::<T>())).await?;
+        Ok(names
+            .iter()
+            .map(|name| name.to_string())
+            .zip(values)
+            .collect())
+    }
+
+    /// Concurrently puts all of the items as JSON with the same metadata and optional expiration
+    /// ttl in seconds, returning the result of each put in the same order as `items`.
+    ///
+    /// The metadata is serialized once and shared by all of the puts, failing to serialize it fails
+    /// the whole call.
+    pub async fn put_many_with_shared_metadata<T: Serialize, M: Serialize>(
+        &self,
+        items: &[(&str, &T)],
+        metadata: &M,
+        ttl: Option<u64>,
+    ) -> Result<Vec<Result<(), KvError>>, KvError> {
+        let metadata = serde_json::to_value(metadata)?;
+        let puts = items.iter().map(|(name, value)| {
+            let metadata = metadata.clone();
+            async move {
+                let mut builder = self.put(name, JsonValue(value))?;
+                builder.metadata = Some(metadata);
+                builder.expiration_ttl = ttl;
+                builder.execute().await
+            }
+        });
+
+        Ok(join_all(puts).await)
+    }
+
+    /// Creates a batch of puts where every key has its own options, e.g. different expirations
+    /// for heterogeneous events. See [`KvStore::put_many_with_shared_metadata`] if the options
+    /// are shared.
+    pub fn multi_put(&self) -> MultiPutBuilder {
+        MultiPutBuilder {
+            store: self.clone(),
+            puts: Vec::new(),
+        }
+    }
+
+    /// Concurrently fetches all of the given keys from this store, then concurrently fetches the
+    /// ones that were not found from `fallback`. Keys missing from both stores are left out of the
+    /// returned map.
+    ///
+    /// If `populate_ttl` is set, values found in `fallback` are written back to this store and
+    /// expire after that many seconds, so the next read is served by this store.
+    pub async fn get_many_with_fallback(
+        &self,
+        names: &[&str],
+        fallback: &KvStore,
+        populate_ttl: Option<u64>,
+    ) -> Result<HashMap<String, KvValue>, KvError> {
+        let values = try_join_all(names.iter().map(|name| self.get(name).value())).await?;
+        let mut found = HashMap::new();
+        let mut missing = Vec::new();
+
+        for (name, value) in names.iter().zip(values) {
+            match value {
+                Some(value) => {
+                    found.insert(name.to_string(), value);
+                }
+                None => missing.push(*name),
+            }
+        }
+
+        let fallback_values =
+            try_join_all(missing.iter().map(|name| fallback.get(name).value())).await?;
+        let fallback_hits: Vec<(&str, KvValue)> = missing
+            .into_iter()
+            .zip(fallback_values)
+            .filter_map(|(name, value)| value.map(|value| (name, value)))
+            .collect();
+
+        if let Some(ttl) = populate_ttl {
+            try_join_all(fallback_hits.iter().map(|(name, value)| async move {
+                self.put_bytes(name, value.as_bytes())?
+                    .expiration_ttl(ttl)
+                    .execute()
+                    .await
+            }))
+            .await?;
+        }
+
+        found.extend(
+            fallback_hits
+                .into_iter()
+                .map(|(name, value)| (name.to_string(), value)),
+        );
+        Ok(found)
+    }
+
+    /// Concurrently fetches `name` from every store, returning the values in the same order as
+    /// `stores`.
+    pub async fn multi_namespace_get(
+        stores: &[&KvStore],
+        name: &str,
+    ) -> Result<Vec<Option<KvValue>>, KvError> {
+        try_join_all(stores.iter().map(|store| store.get(name).value())).await
+    }
+
+    /// Concurrently fetches `name` from every store and returns the value of the first store in
+    /// `stores` that has it, so earlier stores take priority over later ones.
+    ///
+    /// All of the stores are read even if the first one has the value, trading reads for not
+    /// having to wait on each store in turn.
+    pub async fn multi_namespace_get_first(
+        stores: &[&KvStore],
+        name: &str,
+    ) -> Result<Option<KvValue>, KvError> {
+        Ok(Self::multi_namespace_get(stores, name)
+            .await?
+            .into_iter()
+            .flatten()
+            .next())
+    }
+
+    /// Concurrently fetches the JSON values of all the given keys, then concurrently produces the
+    /// missing ones with `compute` and stores them, expiring after `ttl` seconds if set.
+    ///
+    /// Fails if any fetch, computation or put fails, but values computed before the failure may
+    /// already have been stored.
+    pub async fn get_many_or_compute<T, F, Fut>(
+        &self,
+        names: &[&str],
+        ttl: Option<u64>,
+        compute: F,
+    ) -> Result<HashMap<String, T>, KvError>
+    where
+        T: Serialize + DeserializeOwned,
+        F: Fn(&str) -> Fut,
+        Fut: Future<Output = Result<T, KvError>>,
+    {
+        let values = try_join_all(names.iter().map(|name| self.get(name).json::<T>())).await?;
+        let mut found = HashMap::new();
+        let mut missing = Vec::new();
+
+        for (name, value) in names.iter().zip(values) {
+            match value {
+                Some(value) => {
+                    found.insert(name.to_string(), value);
+                }
+                None => missing.push(*name),
+            }
+        }
+
+        let computed = try_join_all(missing.iter().map(|name| {
+            let value = compute(name);
+            async move {
+                let value = value.await?;
+                let mut builder = self.put(name, JsonValue(&value))?;
+                builder.expiration_ttl = ttl;
+                builder.execute().await?;
+                Ok::<_, KvError>((name.to_string(), value))
+            }
+        }))
+        .await?;
+
+        found.extend(computed);
+        Ok(found)
+    }
+
+    /// Concurrently fetches and deserializes the values of all the given keys. Unlike
+    /// [`KvStore::get_multiple_json`], a key that fails to be fetched or deserialized is reported
+    /// in [`BatchGetResult::errors`] instead of failing the whole call.
+    pub async fn get_batch_json<T: DeserializeOwned>(
+        &self,
+        names: &[&str],
+    ) -> Result<BatchGetResult<T>, KvError> {
+        let values = join_all(names.iter().map(|name| self.get(name).text())).await;
+        let mut result = BatchGetResult {
+            values: HashMap::new(),
+            errors: HashMap::new(),
+        };
+
+        for (name, value) in names.iter().zip(values) {
+            let value = value.and_then(|value| match value {
+                Some(value) => Ok(Some(serde_json::from_str(&value)?)),
+                None => Ok(None),
+            });
+
+            match value {
+                Ok(value) => {
+                    result.values.insert(name.to_string(), value);
+                }
+                Err(e) => {
+                    result.errors.insert(name.to_string(), e);
+                }
+            }
+        }
+
+        Ok(result)
+    }
+
+    /// Concurrently fetches and deserializes the values of all the given keys, returning the
+    /// outcome for every key in the same order as `names`.
+    ///
+    /// Like [`KvStore::get_batch_json`] a failing key doesn't fail the others, but the results
+    /// keep the order of the keys and errors sit right next to the values.
+    pub async fn get_many_partial<T: DeserializeOwned>(
+        &self,
+        names: &[&str],
+    ) -> Vec<(String, Result<Option<T>, KvError>)> {
+        let values = join_all(names.iter().map(|name| self.get(name).text())).await;
+
+        names
+            .iter()
+            .zip(values)
+            .map(|(name, value)| {
+                let value = value.and_then(|value| match value {
+                    Some(value) => Ok(Some(serde_json::from_str(&value)?)),
+                    None => Ok(None),
+                });
+                (name.to_string(), value)
+            })
+            .collect()
+    }
+
+    /// Concurrently fetches the values of all the given keys along with their metadata. A key that
+    /// fails to be fetched or whose metadata fails to deserialize is reported in
+    /// [`BatchGetResult::errors`] instead of failing the whole call.
+    ///
+    /// A key stored without metadata is deserialized from `null`, so use an `Option` for `M` if
+    /// not every key has metadata.
+    pub async fn multi_get_with_metadata<M: DeserializeOwned>(
+        &self,
+        names: &[&str],
+    ) -> Result<BatchGetResult<(KvValue, M)>, KvError> {
+        let values = join_all(
+            names
+                .iter()
+                .map(|name| self.get(name).bytes_with_metadata::<Value>()),
+        )
+        .await;
+        let mut result = BatchGetResult {
+            values: HashMap::new(),
+            errors: HashMap::new(),
+        };
+
+        for (name, value) in names.iter().zip(values) {
+            let value = value.and_then(|(value, metadata)| match value {
+                Some(value) => {
+                    let metadata = M::deserialize(metadata.unwrap_or(Value::Null))?;
+                    Ok(Some((KvValue(value), metadata)))
+                }
+                None => Ok(None),
+            });
+
+            match value {
+                Ok(value) => {
+                    result.values.insert(name.to_string(), value);
+                }
+                Err(e) => {
+                    result.errors.insert(name.to_string(), e);
+                }
+            }
+        }
+
+        Ok(result)
+    }
+
+    /// Lists every key, optionally restricted to those starting with `prefix`, along with the first
+    /// `preview_len` characters of its value.
+    ///
+    /// KV has no range reads, so the full values are still fetched and truncated afterwards. The
+    /// preview is best-effort: values are decoded as UTF-8 lossily, and a key changed in between
+    /// listing and fetching shows its new value.
+    pub async fn list_with_value_preview(
+        &self,
+        prefix: Option<&str>,
+        preview_len: usize,
+    ) -> Result<Vec<KvKeyWithPreview>, KvError> {
+        let keys = self.list_keys(prefix).await?;
+        let mut result = Vec::with_capacity(keys.len());
+
+        for batch in keys.chunks(BATCH_SIZE) {
+            let values = try_join_all(batch.iter().map(|key| self.get(&key.name).text())).await?;
+
+            result.extend(
+                batch
+                    .iter()
+                    .zip(values)
+                    .map(|(key, value)| KvKeyWithPreview {
+                        key: key.clone(),
+                        preview: value.map(|value| value.chars().take(preview_len).collect()),
+                    }),
+            );
+        }
+
+        Ok(result)
+    }
+
+    /// Lists up to `limit` keys, optionally restricted to those starting with `prefix`, and
+    /// concurrently fetches and deserializes their values.
+    ///
+    /// Keys whose value fails to deserialize are reported in [`ScanResult::malformed`], and keys
+    /// that are deleted before their value is fetched are left out.
+    pub async fn scan_values<T: DeserializeOwned>(
+        &self,
+        prefix: Option<&str>,
+        limit: Option<u64>,
+    ) -> Result<ScanResult<T>, KvError> {
+        let keys = self.list_keys_limited(prefix, limit).await?;
+        let mut result = ScanResult {
+            values: Vec::with_capacity(keys.len()),
+            malformed: Vec::new(),
+        };
+
+        for batch in keys.chunks(BATCH_SIZE) {
+            let values = try_join_all(batch.iter().map(|key| self.get(&key.name).text())).await?;
+
+            for (key, value) in batch.iter().zip(values) {
+                let value = match value {
+                    Some(value) => value,
+                    None => continue,
+                };
+
+                match serde_json::from_str(&value) {
+                    Ok(value) => result.values.push((key.name.clone(), value)),
+                    Err(_) => result.malformed.push(key.name.clone()),
+                }
+            }
+        }
+
+        Ok(result)
+    }
+
+    /// Puts all of the items yielded by the iterator into the kv store, returning how many puts
+    /// succeeded.
+    ///
+    /// The iterator is driven lazily, only collecting up to 50 items at a time which are then put
+    /// concurrently, so the whole input never has to be held in memory.
+    pub async fn put_many_from_iter<'a, I, V>(&self, items: I) -> Result<usize, KvError>
+    where
+        I: IntoIterator<Item = (&'a str, V)>,
+        V: ToRawKvValue,
+    {
+        let mut items = items.into_iter();
+        let mut succeeded = 0;
+
+        loop {
+            let batch: Vec<(&str, V)> = items.by_ref().take(PUT_BATCH_SIZE).collect();
+            if batch.is_empty() {
+                return Ok(succeeded);
+            }
+
+            let puts = batch
+                .into_iter()
+                .map(|(name, value)| async move { self.put(name, value)?.execute().await });
+            succeeded += join_all(puts)
+                .await
+                .into_iter()
+                .filter(Result::is_ok)
+                .count();
+        }
+    }
+
+    /// Deletes all of the given keys concurrently in batches, returning how many were deleted.
+    pub(crate) async fn delete_all<S: AsRef<str>>(&self, names: &[S]) -> Result<u64, KvError> {
+        for batch in names.chunks(BATCH_SIZE) {
+            try_join_all(batch.iter().map(|name| self.delete(name.as_ref()))).await?;
+        }
+
+        Ok(names.len() as u64)
+    }
+}
@@ -0,0 +1,220 @@
+use std::{future::Future, pin::Pin};
+
+use js_sys::Date;
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::Value;
+use wasm_bindgen_futures::spawn_local;
+
+use crate::{JsonValue, KvError, KvStore, PutOptionsBuilder};
+
+/// The metadata field the time a value was cached at is stored in, in milliseconds since the unix
+/// epoch.
+const CACHED_AT_FIELD: &str = "__cached_at";
+
+/// How failures are cached by [`KvStore::get_or_compute_and_cache`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NegativeCacheConfig {
+    /// How many seconds a failure is cached for, must be at least `60`.
+    pub ttl_secs: u64,
+}
+
+impl KvStore {
+    /// Gets the JSON value stored under `name`, or on a miss produces it with `factory` and stores
+    /// it without an expiration.
+    ///
+    /// `factory` is only called when the key is missing, which makes this a good fit for caching
+    /// the result of an expensive async call such as a fetch or a database query.
+    pub async fn get_or_insert_with<T, F, Fut>(&self, name: &str, factory: F) -> Result<T, KvError>
+    where
+        T: Serialize + DeserializeOwned,
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<T, KvError>>,
+    {
+        self.get_or_insert_with_options(name, |builder| builder, factory)
+            .await
+    }
+
+    /// Like [`KvStore::get_or_insert_with`], but the produced value expires after `ttl` seconds.
+    pub async fn get_or_insert_with_ttl<T, F, Fut>(
+        &self,
+        name: &str,
+        ttl: u64,
+        factory: F,
+    ) -> Result<T, KvError>
+    where
+        T: Serialize + DeserializeOwned,
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<T, KvError>>,
+    {
+        self.get_or_insert_with_options(name, |builder| builder.expiration_ttl(ttl), factory)
+            .await
+    }
+
+    /// Gets the JSON value stored under `name`, or on a miss stores `default` expiring after
+    /// `ttl_secs` seconds and returns it.
+    ///
+    /// This is the cheap counterpart of [`KvStore::get_or_compute_and_cache`] for defaults that
+    /// are already at hand, such as a constant or [`Default::default`].
+    pub async fn get_with_default_and_cache<T>(
+        &self,
+        name: &str,
+        default: T,
+        ttl_secs: u64,
+    ) -> Result<T, KvError>
+    where
+        T: Serialize + DeserializeOwned,
+    {
+        self.get_or_insert_with_options(
+            name,
+            |builder| builder.expiration_ttl(ttl_secs),
+            || async { Ok(default) },
+        )
+        .await
+    }
+
+    /// Gets the JSON value cached under `name`, or on a miss produces it with `compute` and caches
+    /// it for `ttl_secs` seconds.
+    ///
+    /// With a [`NegativeCacheConfig`] a failure of `compute` is cached as well, so for
+    /// [`NegativeCacheConfig::ttl_secs`] seconds the call fails with
+    /// [`KvError::NegativelyCached`] holding the original error message instead of retrying
+    /// `compute`. This keeps a failing backend from being hammered on every request. The error
+    /// message is stored under `{name}__negative`, so `name` itself only ever holds the value.
+    /// Failing to store it doesn't change the outcome, the error of `compute` is returned.
+    pub async fn get_or_compute_and_cache<T, F, Fut>(
+        &self,
+        name: &str,
+        ttl_secs: u64,
+        negative_cache: Option<NegativeCacheConfig>,
+        compute: F,
+    ) -> Result<T, KvError>
+    where
+        T: Serialize + DeserializeOwned,
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<T, KvError>>,
+    {
+        if let Some(cached) = self.get_json(name).await? {
+            return Ok(cached);
+        }
+
+        let negative_name = negative_key(name);
+        if negative_cache.is_some() {
+            if let Some(error) = self.get(&negative_name).text().await? {
+                return Err(KvError::NegativelyCached(error));
+            }
+        }
+
+        match compute().await {
+            Ok(value) => {
+                self.put(name, JsonValue(&value))?
+                    .expiration_ttl(ttl_secs)
+                    .execute()
+                    .await?;
+                Ok(value)
+            }
+            Err(e) => {
+                if let Some(config) = negative_cache {
+                    // Caching the failure is best effort, the caller needs the error of `compute`
+                    // either way.
+                    let cached = async {
+                        self.put(&negative_name, e.to_string())?
+                            .expiration_ttl(config.ttl_secs)
+                            .execute()
+                            .await
+                    }
+                    .await;
+                    if let Err(_cache_error) = cached {
+                        #[cfg(feature = "tracing")]
+                        tracing::warn!(name, error = %_cache_error, "unable to negatively cache");
+                    }
+                }
+                Err(e)
+            }
+        }
+    }
+
+    /// Like [`KvStore::get_or_insert_with`], but the put of the produced value is configured with
+    /// `configure` first, e.g. to set an expiration or metadata.
+    pub async fn get_or_insert_with_options<T, F, Fut, O>(
+        &self,
+        name: &str,
+        configure: O,
+        factory: F,
+    ) -> Result<T, KvError>
+    where
+        T: Serialize + DeserializeOwned,
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<T, KvError>>,
+        O: FnOnce(PutOptionsBuilder) -> PutOptionsBuilder,
+    {
+        if let Some(value) = self.get_json::<T>(name).await? {
+            return Ok(value);
+        }
+
+        let value = factory().await?;
+        configure(self.put_json(name, &value)?).execute().await?;
+        Ok(value)
+    }
+
+    /// Gets the JSON value cached under `name`, refreshing it with `fetch` once it is older than
+    /// `max_age_secs`.
+    ///
+    /// A stale value is still returned straight away while the refresh runs in the background
+    /// with [`spawn_local`], so only a miss waits on `fetch`. Errors from a background refresh
+    /// are dropped and the stale value is kept until the next attempt.
+    pub async fn get_with_stale_while_revalidate<T>(
+        &self,
+        name: &str,
+        max_age_secs: u64,
+        fetch: impl FnOnce() -> Pin<Box<dyn Future<Output = Result<T, KvError>>>>,
+    ) -> Result<T, KvError>
+    where
+        T: Serialize + DeserializeOwned + 'static,
+    {
+        let (cached, metadata) = self.get(name).json_with_metadata::<T, Value>().await?;
+
+        let value = match cached {
+            Some(value) => value,
+            None => {
+                let value = fetch().await?;
+                self.put_cached(name, &value).await?;
+                return Ok(value);
+            }
+        };
+
+        let cached_at = metadata
+            .as_ref()
+            .and_then(|metadata| metadata.get(CACHED_AT_FIELD))
+            .and_then(Value::as_u64);
+        let is_fresh = cached_at.is_some_and(|cached_at| {
+            Date::now() as u64 <= cached_at.saturating_add(max_age_secs.saturating_mul(1000))
+        });
+
+        if !is_fresh {
+            let store = self.clone();
+            let name = name.to_owned();
+            let refresh = fetch();
+            spawn_local(async move {
+                if let Ok(value) = refresh.await {
+                    let _ = store.put_cached(&name, &value).await;
+                }
+            });
+        }
+
+        Ok(value)
+    }
+
+    /// Puts the value as JSON under `name` along with the time it was cached at.
+    async fn put_cached<T: Serialize>(&self, name: &str, value: &T) -> Result<(), KvError> {
+        self.put(name, JsonValue(value))?
+            .reserved_metadata(CACHED_AT_FIELD, (Date::now() as u64).into())
+            .execute()
+            .await
+    }
+}
+
+/// The key the negative cache entry for `name` is stored under by
+/// [`KvStore::get_or_compute_and_cache`].
+fn negative_key(name: &str) -> String {
+    format!("{}__negative", name)
+}
@@ -0,0 +1,221 @@
+use std::num::NonZeroUsize;
+
+use futures_util::future::try_join_all;
+use js_sys::{Date, Math};
+use serde::{Deserialize, Serialize};
+
+use crate::{JsonValue, KvError, KvStore};
+
+/// How many chunks are sent to or fetched from the kv store at once.
+const CHUNK_BATCH_SIZE: usize = 50;
+
+/// The manifest stored under the name of a chunked value.
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+struct ChunkManifest {
+    /// Identifies the chunks of this version of the value, so an overwrite never touches the
+    /// chunks of the value it replaces.
+    generation: u64,
+    total_chunks: usize,
+    total_bytes: usize,
+}
+
+impl KvStore {
+    /// Puts a value too large for a single key by splitting it into chunks of up to
+    /// `chunk_size_bytes` bytes, stored under `{name}__chunk_{generation}_{i}`, along with a
+    /// manifest stored under `name`. Returns the number of chunks.
+    ///
+    /// Every put writes its chunks under a new generation and the manifest last, so a value is
+    /// only readable with [`KvStore::get_chunked`] once all of its chunks are stored, and readers
+    /// of the previous value keep seeing its chunks in the meantime. The chunks of the previous
+    /// value are deleted once the new manifest is stored. This is best effort: if it fails, the
+    /// value is still written and the old chunks are left behind.
+    pub async fn put_chunked(
+        &self,
+        name: &str,
+        value: &[u8],
+        chunk_size_bytes: NonZeroUsize,
+    ) -> Result<usize, KvError> {
+        // Only needed to clean up afterwards, so a value that isn't a manifest is just replaced.
+        let previous = self.get(name).json::<ChunkManifest>().await.unwrap_or(None);
+
+        let generation =
+            ((Date::now() as u64) << 20) | (Math::random() * f64::from(1 << 20)) as u64;
+        let (chunks, manifest) = split_chunks(value, chunk_size_bytes, generation);
+        for (batch_index, batch) in chunks.chunks(CHUNK_BATCH_SIZE).enumerate() {
+            let puts = batch.iter().enumerate().map(|(i, chunk)| async move {
+                let index = batch_index * CHUNK_BATCH_SIZE + i;
+                self.put_bytes(&chunk_key(name, generation, index), chunk)?
+                    .execute()
+                    .await
+            });
+            try_join_all(puts).await?;
+        }
+
+        self.put(name, JsonValue(&manifest))?.execute().await?;
+
+        if let Some(previous) = previous {
+            let _ = self.delete_all(&chunk_keys(name, &previous)).await;
+        }
+
+        Ok(manifest.total_chunks)
+    }
+
+    /// Gets a value stored with [`KvStore::put_chunked`], concurrently fetching and concatenating
+    /// all of its chunks.
+    ///
+    /// Fails with [`KvError::InvalidMetadata`] if a chunk is missing or the chunks don't add up
+    /// to the size in the manifest, e.g. while the value is being overwritten.
+    pub async fn get_chunked(&self, name: &str) -> Result<Option<Vec<u8>>, KvError> {
+        let manifest = match self.get(name).json::<ChunkManifest>().await? {
+            Some(manifest) => manifest,
+            None => return Ok(None),
+        };
+
+        let keys = chunk_keys(name, &manifest);
+        let mut value = Vec::with_capacity(manifest.total_bytes);
+        for batch in keys.chunks(CHUNK_BATCH_SIZE) {
+            let chunks = try_join_all(batch.iter().map(|key| self.get(key).bytes())).await?;
+            for (key, chunk) in batch.iter().zip(chunks) {
+                let chunk = chunk
+                    .ok_or_else(|| KvError::InvalidMetadata(format!("missing chunk {key:?}")))?;
+                value.extend_from_slice(&chunk);
+            }
+        }
+
+        if value.len() != manifest.total_bytes {
+            return Err(KvError::InvalidMetadata(format!(
+                "expected {} bytes for {name:?} but its chunks hold {}",
+                manifest.total_bytes,
+                value.len()
+            )));
+        }
+
+        Ok(Some(value))
+    }
+
+    /// Deletes a value stored with [`KvStore::put_chunked`] along with all of its chunks. The
+    /// manifest is deleted first, so the value stops being readable straight away.
+    pub async fn delete_chunked(&self, name: &str) -> Result<(), KvError> {
+        let manifest = match self.get(name).json::<ChunkManifest>().await? {
+            Some(manifest) => manifest,
+            None => return Ok(()),
+        };
+
+        self.delete(name).await?;
+        self.delete_all(&chunk_keys(name, &manifest)).await?;
+
+        Ok(())
+    }
+}
+
+/// Splits the value into the chunks stored by [`KvStore::put_chunked`], along with the manifest
+/// describing them.
+fn split_chunks(
+    value: &[u8],
+    chunk_size_bytes: NonZeroUsize,
+    generation: u64,
+) -> (Vec<&[u8]>, ChunkManifest) {
+    let chunks: Vec<&[u8]> = value.chunks(chunk_size_bytes.get()).collect();
+    let manifest = ChunkManifest {
+        generation,
+        total_chunks: chunks.len(),
+        total_bytes: value.len(),
+    };
+    (chunks, manifest)
+}
+
+/// The keys all chunks of the value stored under `name` with `manifest` are stored under.
+fn chunk_keys(name: &str, manifest: &ChunkManifest) -> Vec<String> {
+    (0..manifest.total_chunks)
+        .map(|index| chunk_key(name, manifest.generation, index))
+        .collect()
+}
+
+/// The key the chunk at `index` of the given generation of the value stored under `name` is
+/// stored under.
+fn chunk_key(name: &str, generation: u64, index: usize) -> String {
+    format!("{}__chunk_{}_{}", name, generation, index)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn size(bytes: usize) -> NonZeroUsize {
+        NonZeroUsize::new(bytes).unwrap()
+    }
+
+    #[test]
+    fn splits_into_chunks_with_a_shorter_last_chunk() {
+        let (chunks, manifest) = split_chunks(b"abcdefg", size(3), 7);
+
+        assert_eq!(chunks, [&b"abc"[..], b"def", b"g"]);
+        assert_eq!(
+            manifest,
+            ChunkManifest {
+                generation: 7,
+                total_chunks: 3,
+                total_bytes: 7,
+            }
+        );
+    }
+
+    #[test]
+    fn splits_evenly_sized_values_without_an_empty_chunk() {
+        let (chunks, manifest) = split_chunks(b"abcdef", size(3), 7);
+
+        assert_eq!(chunks, [&b"abc"[..], b"def"]);
+        assert_eq!(manifest.total_chunks, 2);
+    }
+
+    #[test]
+    fn splits_empty_values_into_no_chunks() {
+        let (chunks, manifest) = split_chunks(b"", size(3), 7);
+
+        assert!(chunks.is_empty());
+        assert_eq!(
+            manifest,
+            ChunkManifest {
+                generation: 7,
+                total_chunks: 0,
+                total_bytes: 0,
+            }
+        );
+    }
+
+    #[test]
+    fn manifest_round_trips_through_json() {
+        let (_, manifest) = split_chunks(&[0; 10], size(4), 7);
+        let json = serde_json::to_string(&manifest).unwrap();
+
+        assert_eq!(
+            json,
+            r#"{"generation":7,"total_chunks":3,"total_bytes":10}"#
+        );
+        assert_eq!(
+            serde_json::from_str::<ChunkManifest>(&json).unwrap(),
+            manifest
+        );
+    }
+
+    #[test]
+    fn chunk_keys_include_the_generation_and_index() {
+        let (_, manifest) = split_chunks(b"abcde", size(2), 7);
+
+        assert_eq!(
+            chunk_keys("name", &manifest),
+            ["name__chunk_7_0", "name__chunk_7_1", "name__chunk_7_2"]
+        );
+    }
+
+    #[test]
+    fn generations_never_share_chunk_keys() {
+        let (_, old) = split_chunks(b"abcdef", size(2), 1);
+        let (_, new) = split_chunks(b"ab", size(2), 2);
+
+        let old_keys = chunk_keys("name", &old);
+        assert!(chunk_keys("name", &new)
+            .iter()
+            .all(|key| !old_keys.contains(key)));
+    }
+}
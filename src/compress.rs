@@ -0,0 +1,61 @@
+use std::io::{Read, Write};
+
+use flate2::{read::GzDecoder, write::GzEncoder, Compression};
+use serde_json::Value;
+
+use crate::{KvError, KvStore, PutOptionsBuilder};
+
+/// The metadata field the encoding of a compressed value is stored in.
+const ENCODING_FIELD: &str = "__encoding";
+
+/// The encoding [`KvStore::put_compressed`] stores values with.
+const GZIP_ENCODING: &str = "gzip";
+
+impl KvStore {
+    /// Gzips the string and puts the compressed bytes into the kv store, marking them with
+    /// `__encoding: "gzip"` in the metadata so [`KvStore::get_decompressed`] knows to inflate
+    /// them.
+    ///
+    /// Any metadata set on the returned builder must be an object so the encoding can be stored
+    /// alongside it.
+    pub fn put_compressed(&self, name: &str, value: &str) -> Result<PutOptionsBuilder, KvError> {
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder
+            .write_all(value.as_bytes())
+            .map_err(KvError::Compression)?;
+        let compressed = encoder.finish().map_err(KvError::Compression)?;
+
+        Ok(self
+            .put_bytes(name, &compressed)?
+            .reserved_metadata(ENCODING_FIELD, GZIP_ENCODING.into()))
+    }
+
+    /// Gets the string stored under `name`, decompressing it if it was stored with
+    /// [`KvStore::put_compressed`]. Values stored without an encoding are returned as is.
+    pub async fn get_decompressed(&self, name: &str) -> Result<Option<String>, KvError> {
+        let (bytes, metadata) = self.get(name).bytes_with_metadata::<Value>().await?;
+        let bytes = match bytes {
+            Some(bytes) => bytes,
+            None => return Ok(None),
+        };
+
+        let encoding = metadata
+            .as_ref()
+            .and_then(|metadata| metadata.get(ENCODING_FIELD))
+            .and_then(Value::as_str);
+
+        match encoding {
+            None => Ok(Some(String::from_utf8_lossy(&bytes).into_owned())),
+            Some(GZIP_ENCODING) => {
+                let mut value = String::new();
+                GzDecoder::new(bytes.as_slice())
+                    .read_to_string(&mut value)
+                    .map_err(KvError::Compression)?;
+                Ok(Some(value))
+            }
+            Some(encoding) => Err(KvError::InvalidMetadata(format!(
+                "unsupported encoding {encoding:?}"
+            ))),
+        }
+    }
+}
@@ -0,0 +1,297 @@
+use futures_util::future::try_join_all;
+use js_sys::{Date, Math};
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::Value;
+
+use crate::{JsonValue, KvError, KvStore};
+
+/// Metadata that carries a version number, used for optimistic concurrency in
+/// [`KvStore::conditional_update`].
+pub trait VersionedMetadata {
+    /// The current version of the value the metadata is attached to.
+    fn version(&self) -> u64;
+    /// Returns the metadata with its version replaced.
+    fn with_version(self, version: u64) -> Self;
+}
+
+/// The outcome of a [`KvStore::conditional_update`] call.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConditionalUpdateResult<V> {
+    /// The new value was written.
+    Success(V),
+    /// The value was changed by someone else in the meantime, holds the version now stored.
+    Conflict(u64),
+    /// There was no value to update.
+    NotFound,
+    /// The value was stored without metadata, so it has no version to update it against.
+    Unversioned,
+}
+
+/// The outcome of a [`KvStore::compare_metadata_and_update`] call.
+///
+/// Named differently from [`ConditionalUpdateResult`], which reports version conflicts instead.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MetadataUpdateResult<M> {
+    /// The predicate accepted the metadata and the new value was written.
+    Updated,
+    /// The predicate rejected the metadata, which is returned as is.
+    PredicateFailed(M),
+    /// There was no value to update.
+    NotFound,
+}
+
+/// The outcome of a [`KvStore::put_if_value_matches`] call.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConditionalWriteResult {
+    /// The stored value matched and the new value was written.
+    Written,
+    /// The stored value did not match, nothing was written.
+    Mismatch,
+    /// There was no value to compare against, nothing was written.
+    KeyNotFound,
+}
+
+/// The key the sentinel of the batch currently being written by [`KvStore::put_batch_atomic`] is
+/// stored under.
+const BATCH_SENTINEL_KEY: &str = "__batch_sentinel";
+
+/// How many puts of a batch are sent to the kv store at once by [`KvStore::put_batch_atomic`].
+const BATCH_PUT_SIZE: usize = 50;
+
+/// The outcome of a [`KvStore::put_batch_atomic`] call.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AtomicBatchResult {
+    /// Every value of the batch was written, holds the id of the sentinel used.
+    Committed(u64),
+    /// Another batch was already being written, holds the id of its sentinel.
+    Aborted(u64),
+}
+
+impl KvStore {
+    /// Puts the value into the kv store as JSON unless the stored JSON is already identical,
+    /// returning whether a write actually happened.
+    ///
+    /// The comparison is done on the serialized form of the value, so `T` doesn't need to be
+    /// `PartialEq`. This is useful for skipping the redundant writes caused by duplicate events
+    /// without spending write quota.
+    pub async fn idempotent_put<T: Serialize + ?Sized>(
+        &self,
+        name: &str,
+        value: &T,
+    ) -> Result<bool, KvError> {
+        let value = serde_json::to_string(value)?;
+
+        if self.get(name).text().await?.as_ref() == Some(&value) {
+            return Ok(false);
+        }
+
+        self.put(name, value)?.execute().await?;
+        Ok(true)
+    }
+
+    /// Updates a JSON value using its versioned metadata for optimistic concurrency.
+    ///
+    /// The current value and metadata are passed to `f`, and the returned value is written with
+    /// the returned metadata at the next version, but only if the stored version has not changed
+    /// while `f` was running. Since KV has no transactions, this narrows the window for lost
+    /// updates rather than closing it entirely.
+    ///
+    /// The version is read again right before the write, which fetches the whole value a second
+    /// time: KV can only return the metadata of a single key along with its value, and listing
+    /// the key instead is eventually consistent, which would defeat the check.
+    pub async fn conditional_update<V, M>(
+        &self,
+        name: &str,
+        f: impl FnOnce(&V, &M) -> (V, M),
+    ) -> Result<ConditionalUpdateResult<V>, KvError>
+    where
+        V: Serialize + DeserializeOwned,
+        M: Serialize + DeserializeOwned + VersionedMetadata,
+    {
+        let (value, metadata) = match self.get(name).json_with_metadata::<V, M>().await? {
+            (Some(value), Some(metadata)) => (value, metadata),
+            (Some(_), None) => return Ok(ConditionalUpdateResult::Unversioned),
+            (None, _) => return Ok(ConditionalUpdateResult::NotFound),
+        };
+
+        let version = metadata.version();
+        let (new_value, new_metadata) = f(&value, &metadata);
+
+        match self.get(name).text_with_metadata::<M>().await? {
+            (Some(_), Some(current)) if current.version() == version => {}
+            (Some(_), Some(current)) => {
+                return Ok(ConditionalUpdateResult::Conflict(current.version()))
+            }
+            (Some(_), None) => return Ok(ConditionalUpdateResult::Unversioned),
+            (None, _) => return Ok(ConditionalUpdateResult::NotFound),
+        }
+
+        self.put(name, JsonValue(&new_value))?
+            .metadata(new_metadata.with_version(version + 1))?
+            .execute()
+            .await?;
+        Ok(ConditionalUpdateResult::Success(new_value))
+    }
+
+    /// Puts the new value as JSON, keeping the current metadata, but only if `metadata_predicate`
+    /// accepts the current metadata of the key, e.g. to only update entries whose status is still
+    /// `"pending"`.
+    ///
+    /// A key stored without metadata has its metadata deserialized from `null`, so use an
+    /// `Option` for `M` if not every key has metadata. Like [`KvStore::conditional_update`] this
+    /// narrows the window for lost updates rather than closing it, and the current expiration of
+    /// the key is not kept.
+    pub async fn compare_metadata_and_update<V, M, F>(
+        &self,
+        name: &str,
+        new_value: &V,
+        metadata_predicate: F,
+    ) -> Result<MetadataUpdateResult<M>, KvError>
+    where
+        V: Serialize,
+        M: DeserializeOwned + Serialize,
+        F: Fn(&M) -> bool,
+    {
+        let (value, metadata) = self.get(name).text_with_metadata::<Value>().await?;
+        if value.is_none() {
+            return Ok(MetadataUpdateResult::NotFound);
+        }
+
+        let metadata = M::deserialize(metadata.unwrap_or(Value::Null))?;
+        if !metadata_predicate(&metadata) {
+            return Ok(MetadataUpdateResult::PredicateFailed(metadata));
+        }
+
+        self.put(name, JsonValue(new_value))?
+            .metadata(&metadata)?
+            .execute()
+            .await?;
+        Ok(MetadataUpdateResult::Updated)
+    }
+
+    /// Puts the new value as JSON, but only if the value currently stored deserializes to one equal
+    /// to `expected_value`, a compare-and-swap on the content instead of a version number.
+    ///
+    /// A stored value that fails to deserialize to `T` fails the call. Like every
+    /// read-modify-write on KV this narrows the window for lost updates rather than closing it.
+    pub async fn put_if_value_matches<T>(
+        &self,
+        name: &str,
+        expected_value: &T,
+        new_value: &T,
+    ) -> Result<ConditionalWriteResult, KvError>
+    where
+        T: Serialize + DeserializeOwned + PartialEq,
+    {
+        let current = match self.get(name).text().await? {
+            Some(current) => serde_json::from_str::<T>(&current)?,
+            None => return Ok(ConditionalWriteResult::KeyNotFound),
+        };
+        if current != *expected_value {
+            return Ok(ConditionalWriteResult::Mismatch);
+        }
+
+        self.put(name, JsonValue(new_value))?.execute().await?;
+        Ok(ConditionalWriteResult::Written)
+    }
+
+    /// Puts the value as JSON, keeping the current expiration of the key, but only if that
+    /// expiration still is `expected_expiration`. Returns whether the value was written.
+    ///
+    /// This is a weak form of compare-and-swap for cache refreshes, where the expiration read
+    /// alongside a value serves as its version.
+    pub async fn put_if_expiration_matches<V: Serialize>(
+        &self,
+        name: &str,
+        value: &V,
+        expected_expiration: u64,
+    ) -> Result<bool, KvError> {
+        let current = self.find_key(name).await?.and_then(|key| key.expiration);
+        if current != Some(expected_expiration) {
+            return Ok(false);
+        }
+
+        self.put(name, JsonValue(value))?
+            .expiration(expected_expiration)
+            .execute()
+            .await?;
+        Ok(true)
+    }
+
+    /// Puts the value as JSON expiring in `new_ttl_secs` seconds if the key is missing or has
+    /// already expired but was not cleaned up yet. Returns whether the value was written.
+    ///
+    /// The key and its expiration are looked up with a single list request, as `getWithMetadata`
+    /// doesn't return the expiration of a key.
+    pub async fn put_if_missing_or_expired<V: Serialize>(
+        &self,
+        name: &str,
+        value: &V,
+        new_ttl_secs: u64,
+    ) -> Result<bool, KvError> {
+        let now = (Date::now() / 1000.0) as u64;
+        let live = match self.find_key(name).await? {
+            Some(key) => key.expiration.is_none_or(|expiration| expiration > now),
+            None => false,
+        };
+        if live {
+            return Ok(false);
+        }
+
+        self.put(name, JsonValue(value))?
+            .expiration_ttl(new_ttl_secs)
+            .execute()
+            .await?;
+        Ok(true)
+    }
+
+    /// Puts every value of the batch while holding a sentinel key, aborting without writing
+    /// anything if another batch already holds it. The sentinel expires after `ttl_secs` seconds,
+    /// which must be at least `60`, so a worker that dies mid-batch can't block writers forever.
+    ///
+    /// This is not a transaction: readers can observe a partially written batch, a failed put
+    /// leaves the earlier ones in place, and two writers that check for the sentinel at the same
+    /// time can both go ahead. It only stops the common case of naively concurrent batches.
+    ///
+    /// Fails with [`KvError::InvalidMetadata`] if the sentinel key holds something other than a
+    /// batch id, e.g. because it was written by hand.
+    pub async fn put_batch_atomic(
+        &self,
+        batch: Vec<(&str, String)>,
+        ttl_secs: u64,
+    ) -> Result<AtomicBatchResult, KvError> {
+        if let Some(existing) = self.get(BATCH_SENTINEL_KEY).text().await? {
+            let id = existing.parse().map_err(|_| {
+                KvError::InvalidMetadata(format!(
+                    "{BATCH_SENTINEL_KEY:?} holds {existing:?} instead of a batch id"
+                ))
+            })?;
+            return Ok(AtomicBatchResult::Aborted(id));
+        }
+
+        let id = ((Date::now() as u64) << 20) | (Math::random() * f64::from(1 << 20)) as u64;
+        self.put(BATCH_SENTINEL_KEY, id.to_string())?
+            .expiration_ttl(ttl_secs)
+            .execute()
+            .await?;
+
+        let result = self.put_batch(&batch).await;
+        let released = self.delete(BATCH_SENTINEL_KEY).await;
+        result?;
+        released?;
+
+        Ok(AtomicBatchResult::Committed(id))
+    }
+
+    async fn put_batch(&self, batch: &[(&str, String)]) -> Result<(), KvError> {
+        for chunk in batch.chunks(BATCH_PUT_SIZE) {
+            let puts = chunk
+                .iter()
+                .map(|(name, value)| self.put(name, value))
+                .collect::<Result<Vec<_>, _>>()?;
+            try_join_all(puts.into_iter().map(|builder| builder.execute())).await?;
+        }
+
+        Ok(())
+    }
+}
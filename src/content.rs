@@ -0,0 +1,158 @@
+use js_sys::Promise;
+use serde_json::Value;
+use wasm_bindgen_futures::JsFuture;
+use web_sys::{Headers, Response, ResponseInit};
+
+use crate::{KvError, KvStore, KvValue, PutOptionsBuilder, ToRawKvValue};
+
+/// The metadata field the content type of a value is stored in.
+pub(crate) const CONTENT_TYPE_FIELD: &str = "__content_type";
+
+/// The metadata field the status of a cached response is stored in.
+const STATUS_FIELD: &str = "__status";
+
+/// The metadata field the `Cache-Control` header of a cached response is stored in.
+const CACHE_CONTROL_FIELD: &str = "__cache_control";
+
+/// The content type assumed for values stored without one.
+const DEFAULT_CONTENT_TYPE: &str = "application/octet-stream";
+
+/// A value decoded according to the content type it was stored with, see
+/// [`KvStore::get_decoded`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum DecodedKvValue {
+    /// A value with a `text/*` content type.
+    Text(String),
+    /// A value with an `application/json` content type.
+    Json(Value),
+    /// A value with any other content type, such as `application/octet-stream`.
+    Binary(Vec<u8>),
+}
+
+impl KvStore {
+    /// Puts data into the kv store along with the content type it should be served with, which is
+    /// stored in the `__content_type` metadata field.
+    ///
+    /// Any metadata set on the returned builder must be an object so the content type can be
+    /// stored alongside it.
+    pub fn put_with_content_type_hint(
+        &self,
+        name: &str,
+        value: impl ToRawKvValue,
+        content_type: &str,
+    ) -> Result<PutOptionsBuilder, KvError> {
+        Ok(self
+            .put(name, value)?
+            .reserved_metadata(CONTENT_TYPE_FIELD, content_type.into()))
+    }
+
+    /// Gets the value along with the content type it was stored with by
+    /// [`KvStore::put_with_content_type_hint`], which is `application/octet-stream` if it was
+    /// stored without one.
+    pub async fn get_with_content_type(
+        &self,
+        name: &str,
+    ) -> Result<Option<(KvValue, String)>, KvError> {
+        let (value, metadata) = self.get(name).bytes_with_metadata::<Value>().await?;
+        Ok(value.map(|value| (KvValue(value), content_type(metadata.as_ref()))))
+    }
+
+    /// Gets the value decoded according to the content type it was stored with by
+    /// [`KvStore::put_with_content_type_hint`]. Values without a content type are returned as
+    /// [`DecodedKvValue::Binary`].
+    pub async fn get_decoded(&self, name: &str) -> Result<Option<DecodedKvValue>, KvError> {
+        let (value, content_type) = match self.get_with_content_type(name).await? {
+            Some(value) => value,
+            None => return Ok(None),
+        };
+
+        let mime_type = content_type.split(';').next().unwrap_or_default().trim();
+        let decoded = if mime_type.starts_with("text/") {
+            DecodedKvValue::Text(value.as_string())
+        } else if mime_type == "application/json" {
+            DecodedKvValue::Json(value.as_json()?)
+        } else {
+            DecodedKvValue::Binary(value.0)
+        };
+
+        Ok(Some(decoded))
+    }
+}
+
+impl KvStore {
+    /// Reads the body of the response as text and puts it into the kv store, along with its
+    /// status, `Content-Type` and `Cache-Control` headers in the `__status`, `__content_type` and
+    /// `__cache_control` metadata fields so it can be rebuilt by [`KvStore::get_as_response`].
+    ///
+    /// Any metadata set on the returned builder must be an object so the response details can be
+    /// stored alongside it.
+    pub async fn put_from_response(
+        &self,
+        name: &str,
+        response: Response,
+    ) -> Result<PutOptionsBuilder, KvError> {
+        let headers = response.headers();
+        let content_type = headers.get("content-type")?;
+        let cache_control = headers.get("cache-control")?;
+
+        let text: Promise = response.text()?;
+        let body = JsFuture::from(text).await?.as_string().unwrap_or_default();
+
+        let mut builder = self
+            .put(name, body)?
+            .reserved_metadata(STATUS_FIELD, response.status().into());
+        if let Some(content_type) = content_type {
+            builder = builder.reserved_metadata(CONTENT_TYPE_FIELD, content_type.into());
+        }
+        if let Some(cache_control) = cache_control {
+            builder = builder.reserved_metadata(CACHE_CONTROL_FIELD, cache_control.into());
+        }
+
+        Ok(builder)
+    }
+
+    /// Rebuilds a response stored with [`KvStore::put_from_response`] from its body, status and
+    /// headers. Values stored without a status are returned with a `200` status.
+    pub async fn get_as_response(&self, name: &str) -> Result<Option<Response>, KvError> {
+        let (body, metadata) = self.get(name).text_with_metadata::<Value>().await?;
+        let body = match body {
+            Some(body) => body,
+            None => return Ok(None),
+        };
+
+        let field = |name: &str| {
+            metadata
+                .as_ref()
+                .and_then(|metadata| metadata.get(name))
+                .cloned()
+        };
+
+        let headers = Headers::new()?;
+        if let Some(Value::String(content_type)) = field(CONTENT_TYPE_FIELD) {
+            headers.set("content-type", &content_type)?;
+        }
+        if let Some(Value::String(cache_control)) = field(CACHE_CONTROL_FIELD) {
+            headers.set("cache-control", &cache_control)?;
+        }
+
+        let status = field(STATUS_FIELD)
+            .and_then(|status| status.as_u64())
+            .unwrap_or(200);
+        let init = ResponseInit::new();
+        init.set_status(status as u16);
+        init.set_headers(&headers);
+
+        Ok(Some(Response::new_with_opt_str_and_init(
+            Some(&body),
+            &init,
+        )?))
+    }
+}
+
+pub(crate) fn content_type(metadata: Option<&Value>) -> String {
+    metadata
+        .and_then(|metadata| metadata.get(CONTENT_TYPE_FIELD))
+        .and_then(Value::as_str)
+        .unwrap_or(DEFAULT_CONTENT_TYPE)
+        .into()
+}
@@ -0,0 +1,84 @@
+use js_sys::{Date, Math};
+use serde::Serialize;
+
+use crate::{JsonValue, KvError, KvStore};
+
+impl KvStore {
+    /// Stores `candidate` under `name` if it is greater than the number currently stored, keeping
+    /// a high-water mark. A missing key counts as [`i64::MIN`]. Returns the number stored
+    /// afterwards.
+    ///
+    /// Like every read-modify-write on KV this is not actually atomic, concurrent writers from
+    /// other locations can still overwrite each other.
+    pub async fn atomic_max(&self, name: &str, candidate: i64) -> Result<i64, KvError> {
+        let current = self.get_number(name).await?.unwrap_or(i64::MIN);
+        if candidate > current {
+            self.put_number(name, candidate).await?;
+            Ok(candidate)
+        } else {
+            Ok(current)
+        }
+    }
+
+    /// Stores `candidate` under `name` if it is less than the number currently stored, keeping a
+    /// low-water mark. A missing key counts as [`i64::MAX`]. Returns the number stored afterwards.
+    ///
+    /// Like every read-modify-write on KV this is not actually atomic, concurrent writers from
+    /// other locations can still overwrite each other.
+    pub async fn atomic_min(&self, name: &str, candidate: i64) -> Result<i64, KvError> {
+        let current = self.get_number(name).await?.unwrap_or(i64::MAX);
+        if candidate < current {
+            self.put_number(name, candidate).await?;
+            Ok(candidate)
+        } else {
+            Ok(current)
+        }
+    }
+
+    /// Puts the value as JSON under a newly generated `{prefix}/{timestamp_ms}/{random_suffix}`
+    /// key and returns that key. The timestamp is zero padded so keys list in insertion order.
+    pub async fn put_unique<T: Serialize>(
+        &self,
+        prefix: &str,
+        value: &T,
+    ) -> Result<String, KvError> {
+        let suffix = (Math::random() * f64::from(u32::MAX)) as u32;
+        let name = format!("{}/{:013}/{:08x}", prefix, Date::now() as u64, suffix);
+
+        self.put(&name, JsonValue(value))?.execute().await?;
+        Ok(name)
+    }
+
+    /// Puts the value as JSON under a `{prefix}/{sequence}` key, where the sequence number is
+    /// incremented from a counter stored under `__counter/{prefix}`, and returns that key along
+    /// with its sequence number. The sequence number is zero padded so keys list in order.
+    ///
+    /// Incrementing the counter is a read-modify-write, so concurrent writers can end up with the
+    /// same sequence number. Prefer [`KvStore::put_unique`] if keys must never collide.
+    pub async fn put_unique_sequential<T: Serialize>(
+        &self,
+        prefix: &str,
+        value: &T,
+    ) -> Result<(String, u64), KvError> {
+        let counter = format!("__counter/{}", prefix);
+        let sequence = self.get_number(&counter).await?.unwrap_or(0) + 1;
+        self.put_number(&counter, sequence).await?;
+
+        let name = format!("{}/{:020}", prefix, sequence);
+        self.put(&name, JsonValue(value))?.execute().await?;
+        Ok((name, sequence as u64))
+    }
+
+    /// Gets the number stored as text under `name`.
+    pub(crate) async fn get_number(&self, name: &str) -> Result<Option<i64>, KvError> {
+        match self.get(name).text().await? {
+            Some(text) => Ok(Some(serde_json::from_str(&text)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Stores a number as text under `name`.
+    pub(crate) async fn put_number(&self, name: &str, number: i64) -> Result<(), KvError> {
+        self.put(name, number.to_string())?.execute().await
+    }
+}
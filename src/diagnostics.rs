@@ -0,0 +1,236 @@
+use std::collections::HashMap;
+
+use futures_util::future::{join_all, try_join_all};
+use js_sys::{Date, Math};
+use serde::de::DeserializeOwned;
+
+use crate::{Key, KvError, KvStore};
+
+/// The key written and removed again by [`KvStore::diagnose`].
+const DIAGNOSTICS_KEY: &str = "__worker_kv_diagnostics";
+
+/// How many values are measured at once by [`KvStore::estimate_storage_usage`].
+const SAMPLE_BATCH_SIZE: usize = 50;
+
+/// The outcome of the test operations run by [`KvStore::diagnose`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct KvDiagnostics {
+    /// If the test value could be put into the store.
+    pub put_ok: bool,
+    /// If the test value could be read back from the store.
+    pub get_ok: bool,
+    /// If the test key could be found by listing the store.
+    pub list_ok: bool,
+    /// If the test key could be deleted from the store.
+    pub delete_ok: bool,
+    /// A description of every operation that failed.
+    pub errors: Vec<String>,
+}
+
+impl KvDiagnostics {
+    /// Returns `true` if every operation succeeded.
+    pub fn is_healthy(&self) -> bool {
+        self.put_ok && self.get_ok && self.list_ok && self.delete_ok
+    }
+}
+
+/// The outcome of a [`KvStore::verify_consistency`] call.
+#[derive(Debug, Default)]
+pub struct ConsistencyReport {
+    /// The keys that exist and could be deserialized.
+    pub present: Vec<String>,
+    /// The keys that don't exist.
+    pub missing: Vec<String>,
+    /// The keys that could not be fetched or deserialized along with the reason.
+    pub malformed: Vec<(String, KvError)>,
+}
+
+impl ConsistencyReport {
+    /// Returns `true` if every required key exists and could be deserialized.
+    pub fn is_consistent(&self) -> bool {
+        self.missing.is_empty() && self.malformed.is_empty()
+    }
+}
+
+/// An approximation of the storage used by a kv store, see [`KvStore::estimate_storage_usage`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct StorageUsage {
+    /// The number of keys.
+    pub key_count: u64,
+    /// The estimated total size of the values in bytes.
+    pub estimated_value_bytes: u64,
+    /// The total size of the metadata serialized as JSON in bytes.
+    pub metadata_bytes: u64,
+}
+
+/// How long until a key expires, as grouped by [`KvStore::list_expiration_report`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ExpirationBucket {
+    /// The expiration has already passed, but the key was not cleaned up yet.
+    Expired,
+    /// Expires within the next hour.
+    LessThanHour,
+    /// Expires in one hour to one day.
+    HourToDay,
+    /// Expires in one day to one week.
+    DayToWeek,
+    /// Expires in more than one week.
+    MoreThanWeek,
+    /// Never expires.
+    Never,
+}
+
+impl ExpirationBucket {
+    /// The bucket a key expiring at `expiration`, a unix timestamp in seconds, falls into at
+    /// `now`.
+    fn of(expiration: Option<u64>, now: u64) -> Self {
+        const HOUR: u64 = 60 * 60;
+        const DAY: u64 = 24 * HOUR;
+
+        match expiration {
+            None => Self::Never,
+            Some(expiration) if expiration <= now => Self::Expired,
+            Some(expiration) => match expiration - now {
+                remaining if remaining < HOUR => Self::LessThanHour,
+                remaining if remaining < DAY => Self::HourToDay,
+                remaining if remaining < 7 * DAY => Self::DayToWeek,
+                _ => Self::MoreThanWeek,
+            },
+        }
+    }
+}
+
+/// The keys of a kv store grouped by how long until they expire, see
+/// [`KvStore::list_expiration_report`].
+#[derive(Debug, Clone, Default)]
+pub struct ExpirationReport {
+    /// The keys in every bucket that has any, in listing order.
+    pub buckets: HashMap<ExpirationBucket, Vec<Key>>,
+}
+
+impl KvStore {
+    /// Runs a put, get, list and delete of a test key to check that the store is usable, e.g. as
+    /// a health check when a worker starts.
+    ///
+    /// The report is returned even if some of the operations fail. Keep in mind that the test key
+    /// might not be visible to the get and list yet if the store is slow to propagate writes.
+    pub async fn diagnose(&self) -> Result<KvDiagnostics, KvError> {
+        let mut diagnostics = KvDiagnostics::default();
+
+        match self.put(DIAGNOSTICS_KEY, "ok")?.execute().await {
+            Ok(()) => diagnostics.put_ok = true,
+            Err(e) => diagnostics.errors.push(format!("put failed: {}", e)),
+        }
+
+        match self.get(DIAGNOSTICS_KEY).text().await {
+            Ok(Some(value)) if value == "ok" => diagnostics.get_ok = true,
+            Ok(value) => diagnostics
+                .errors
+                .push(format!("get returned an unexpected value: {:?}", value)),
+            Err(e) => diagnostics.errors.push(format!("get failed: {}", e)),
+        }
+
+        match self.find_key(DIAGNOSTICS_KEY).await {
+            Ok(Some(_)) => diagnostics.list_ok = true,
+            Ok(None) => diagnostics
+                .errors
+                .push("list did not include the test key".into()),
+            Err(e) => diagnostics.errors.push(format!("list failed: {}", e)),
+        }
+
+        match self.delete(DIAGNOSTICS_KEY).await {
+            Ok(()) => diagnostics.delete_ok = true,
+            Err(e) => diagnostics.errors.push(format!("delete failed: {}", e)),
+        }
+
+        Ok(diagnostics)
+    }
+
+    /// Checks that all of the required keys exist and hold JSON that deserializes to `T`, e.g. so
+    /// a worker can refuse to serve requests at startup instead of failing on first access.
+    pub async fn verify_consistency<T: DeserializeOwned>(
+        &self,
+        required_keys: &[&str],
+    ) -> Result<ConsistencyReport, KvError> {
+        let values = join_all(required_keys.iter().map(|name| self.get(name).text())).await;
+        let mut report = ConsistencyReport::default();
+
+        for (name, value) in required_keys.iter().zip(values) {
+            let name = name.to_string();
+            match value {
+                Ok(Some(value)) => match serde_json::from_str::<T>(&value) {
+                    Ok(_) => report.present.push(name),
+                    Err(e) => report.malformed.push((name, e.into())),
+                },
+                Ok(None) => report.missing.push(name),
+                Err(e) => report.malformed.push((name, e)),
+            }
+        }
+
+        Ok(report)
+    }
+
+    /// Estimates how much storage the keys, optionally restricted to those starting with
+    /// `prefix`, take up. This is meant for monitoring, not as a billing meter.
+    ///
+    /// The metadata is part of the listing and is measured exactly, but measuring a value
+    /// requires reading it, so only a random `sample_rate` fraction (between `0.0` and `1.0`) of
+    /// the values are measured with [`KvStore::key_size_hint`] and their average size is
+    /// extrapolated to all keys.
+    pub async fn estimate_storage_usage(
+        &self,
+        prefix: Option<&str>,
+        sample_rate: f32,
+    ) -> Result<StorageUsage, KvError> {
+        let keys = self.list_keys(prefix).await?;
+        let metadata_bytes = keys
+            .iter()
+            .filter_map(|key| key.metadata.as_ref())
+            .map(|metadata| metadata.to_string().len() as u64)
+            .sum();
+
+        let sample_rate = f64::from(sample_rate.clamp(0.0, 1.0));
+        let sample: Vec<&str> = keys
+            .iter()
+            .filter(|_| Math::random() < sample_rate)
+            .map(|key| key.name.as_str())
+            .collect();
+
+        let mut sampled_bytes = 0;
+        for batch in sample.chunks(SAMPLE_BATCH_SIZE) {
+            let sizes = try_join_all(batch.iter().map(|name| self.key_size_hint(name))).await?;
+            sampled_bytes += sizes.into_iter().flatten().sum::<u64>();
+        }
+
+        let estimated_value_bytes = match sample.len() {
+            0 => 0,
+            sampled => (sampled_bytes as f64 / sampled as f64 * keys.len() as f64) as u64,
+        };
+
+        Ok(StorageUsage {
+            key_count: keys.len() as u64,
+            estimated_value_bytes,
+            metadata_bytes,
+        })
+    }
+
+    /// Lists every key, optionally restricted to those starting with `prefix`, and groups them by
+    /// how long until they expire according to `Date.now()`.
+    pub async fn list_expiration_report(
+        &self,
+        prefix: Option<&str>,
+    ) -> Result<ExpirationReport, KvError> {
+        let now = (Date::now() / 1000.0) as u64;
+        let mut report = ExpirationReport::default();
+
+        for key in self.list_keys(prefix).await? {
+            report
+                .buckets
+                .entry(ExpirationBucket::of(key.expiration, now))
+                .or_default()
+                .push(key);
+        }
+
+        Ok(report)
+    }
+}
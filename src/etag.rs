@@ -0,0 +1,66 @@
+use serde::Serialize;
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+
+use crate::{hex, KvError, KvStore, KvValue};
+
+/// The metadata field the ETag of a value is stored in.
+const ETAG_FIELD: &str = "__etag";
+
+/// How many bytes of the SHA-256 hash make up an ETag, hex encoded to twice as many characters.
+const ETAG_BYTES: usize = 8;
+
+impl KvStore {
+    /// Puts the value as JSON along with an ETag, the first 16 hex characters of the SHA-256 hash
+    /// of the JSON, stored under `__etag` in the metadata. Returns the ETag.
+    pub async fn put_with_etag<T: Serialize>(
+        &self,
+        name: &str,
+        value: &T,
+    ) -> Result<String, KvError> {
+        let value = serde_json::to_string(value)?;
+        let etag = hex::encode(&Sha256::digest(value.as_bytes())[..ETAG_BYTES]);
+
+        self.put(name, value)?
+            .reserved_metadata(ETAG_FIELD, etag.clone().into())
+            .execute()
+            .await?;
+        Ok(etag)
+    }
+
+    /// Gets the ETag stored with [`KvStore::put_with_etag`] without fetching the value, by
+    /// reading the metadata from the listing of the key.
+    ///
+    /// Listings are eventually consistent, so a value that was just put may briefly report its
+    /// previous ETag or none at all.
+    pub async fn get_etag(&self, name: &str) -> Result<Option<String>, KvError> {
+        Ok(self
+            .find_key(name)
+            .await?
+            .and_then(|key| etag(key.metadata.as_ref()).map(str::to_owned)))
+    }
+
+    /// Gets the value stored under `name` unless its ETag matches `etag`, the equivalent of a
+    /// `304 Not Modified` response to an `If-None-Match` request.
+    ///
+    /// `None` is also returned if the key is missing, so check [`KvStore::get_etag`] first if the
+    /// two need to be told apart.
+    pub async fn get_if_none_match(
+        &self,
+        name: &str,
+        etag: &str,
+    ) -> Result<Option<KvValue>, KvError> {
+        let (value, metadata) = self.get(name).bytes_with_metadata::<Value>().await?;
+        if self::etag(metadata.as_ref()) == Some(etag) {
+            return Ok(None);
+        }
+
+        Ok(value.map(KvValue))
+    }
+}
+
+fn etag(metadata: Option<&Value>) -> Option<&str> {
+    metadata
+        .and_then(|metadata| metadata.get(ETAG_FIELD))
+        .and_then(Value::as_str)
+}
@@ -0,0 +1,45 @@
+//! The hex encoding used for signatures, ETags and page tokens.
+
+/// Encodes the bytes as a lowercase hex string.
+pub(crate) fn encode(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+/// Decodes a hex string in either case, returning `None` if it is malformed.
+pub(crate) fn decode(hex: &str) -> Option<Vec<u8>> {
+    if !hex.len().is_multiple_of(2) {
+        return None;
+    }
+
+    hex.as_bytes()
+        .chunks(2)
+        .map(|pair| Some((digit(pair[0])? << 4) | digit(pair[1])?))
+        .collect()
+}
+
+fn digit(c: u8) -> Option<u8> {
+    (c as char).to_digit(16).map(|digit| digit as u8)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trips() {
+        let bytes = [0x00, 0x0f, 0xa0, 0xff];
+
+        assert_eq!(encode(&bytes), "000fa0ff");
+        assert_eq!(decode("000fa0ff").unwrap(), bytes);
+        assert_eq!(decode("000FA0FF").unwrap(), bytes);
+        assert!(decode("").unwrap().is_empty());
+    }
+
+    #[test]
+    fn rejects_malformed_hex() {
+        assert_eq!(decode("abc"), None);
+        assert_eq!(decode("zz"), None);
+        assert_eq!(decode("+f"), None);
+        assert_eq!(decode("é"), None);
+    }
+}
@@ -0,0 +1,76 @@
+use std::rc::Rc;
+
+use js_sys::{Function, Object, Promise};
+use wasm_bindgen::{closure::Closure, JsCast, JsValue};
+use wasm_bindgen_futures::{spawn_local, JsFuture};
+use web_sys::console;
+
+use crate::KvStore;
+
+impl KvStore {
+    /// Returns a store that logs every operation to the console before it starts and after it
+    /// finishes, prefixed with `label` if given, which is handy while debugging a worker.
+    ///
+    /// This is a no-op that returns the store unchanged when `debug_assertions` are disabled, so
+    /// it can be left in place for release builds. Keep in mind that the logging wrappers are
+    /// never freed, so avoid calling this repeatedly in a long running worker.
+    pub fn inspect(self, label: Option<&str>) -> KvStore {
+        if !cfg!(debug_assertions) {
+            return self;
+        }
+
+        let prefix: Rc<str> = match label {
+            Some(label) => format!("[{}] ", label).into(),
+            None => "".into(),
+        };
+        let wrap = |function: &Function, operation: &'static str| {
+            inspect_function(&self.this, function, operation, prefix.clone())
+        };
+
+        KvStore {
+            get_function: wrap(&self.get_function, "get"),
+            get_with_meta_function: wrap(&self.get_with_meta_function, "getWithMetadata"),
+            put_function: wrap(&self.put_function, "put"),
+            list_function: wrap(&self.list_function, "list"),
+            delete_function: wrap(&self.delete_function, "delete"),
+            this: self.this.clone(),
+            #[cfg(feature = "tracing")]
+            binding: self.binding.clone(),
+        }
+    }
+}
+
+/// Wraps a kv function so that its calls and their outcome are logged to the console.
+fn inspect_function(
+    this: &Object,
+    function: &Function,
+    operation: &'static str,
+    prefix: Rc<str>,
+) -> Function {
+    let this = this.clone();
+    let function = function.clone();
+
+    let closure = Closure::wrap(
+        Box::new(move |first: JsValue, second: JsValue, third: JsValue| {
+            // Every operation but list takes the key name as its first argument.
+            let call = match first.as_string() {
+                Some(name) => format!("{}kv {} {:?}", prefix, operation, name),
+                None => format!("{}kv {}", prefix, operation),
+            };
+            console::log_1(&JsValue::from(format!("{} started", call)));
+
+            let promise: Promise = function.call3(&this, &first, &second, &third)?.into();
+            let future = JsFuture::from(promise.clone());
+            spawn_local(async move {
+                match future.await {
+                    Ok(_) => console::log_1(&JsValue::from(format!("{} finished", call))),
+                    Err(e) => console::log_2(&JsValue::from(format!("{} failed:", call)), &e),
+                }
+            });
+
+            Ok(promise.into())
+        }) as Box<dyn Fn(JsValue, JsValue, JsValue) -> Result<JsValue, JsValue>>,
+    );
+
+    closure.into_js_value().unchecked_into()
+}
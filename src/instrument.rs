@@ -0,0 +1,42 @@
+use std::{future::Future, sync::Arc};
+
+use js_sys::Date;
+use tracing::Instrument;
+
+use crate::KvError;
+
+/// Runs a kv operation inside a span carrying the OpenTelemetry database semantic convention
+/// fields, then records how long it took and, for reads, whether the key was found.
+pub(crate) async fn instrument<T, Fut>(
+    operation: &'static str,
+    binding: Arc<str>,
+    key: Option<String>,
+    is_hit: fn(&T) -> Option<bool>,
+    future: Fut,
+) -> Result<T, KvError>
+where
+    Fut: Future<Output = Result<T, KvError>>,
+{
+    let span = tracing::info_span!(
+        "kv",
+        otel.name = operation,
+        db.system = "cloudflare_kv",
+        db.name = &*binding,
+        db.operation = operation,
+        db.key = key.as_deref(),
+    );
+
+    let start = Date::now();
+    let result = future.instrument(span.clone()).await;
+    let latency_ms = Date::now() - start;
+
+    match &result {
+        Ok(value) => match is_hit(value) {
+            Some(hit) => tracing::info!(parent: &span, latency_ms, hit, "kv operation finished"),
+            None => tracing::info!(parent: &span, latency_ms, "kv operation finished"),
+        },
+        Err(e) => tracing::error!(parent: &span, latency_ms, error = %e, "kv operation failed"),
+    }
+
+    result
+}
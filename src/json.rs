@@ -0,0 +1,212 @@
+use serde::Serialize;
+use serde_json::{Map, Value};
+
+use crate::{KvError, KvStore};
+
+impl KvStore {
+    /// Updates the JSON document stored under `name` by merging `patch` into it following
+    /// [JSON Merge Patch](https://www.rfc-editor.org/rfc/rfc7396) semantics.
+    ///
+    /// Fields set to `null` in `patch` are removed from the document, fields missing from `patch`
+    /// are preserved and a missing document is treated as an empty one. The document is written
+    /// back without its previous metadata or expiration.
+    pub async fn put_json_patch<T: Serialize>(&self, name: &str, patch: &T) -> Result<(), KvError> {
+        let mut document = self.get_json_document(name).await?.unwrap_or(Value::Null);
+        merge_patch(&mut document, serde_json::to_value(patch)?);
+        self.put(name, &document)?.execute().await
+    }
+
+    /// Gets a single field of the JSON document stored under `name`, addressed by a dot separated
+    /// path such as `"address.city"`. Numeric segments also index into arrays.
+    ///
+    /// Returns `None` if the document or the field is missing. The whole document still has to
+    /// be fetched, this only saves deserializing it into a full type.
+    pub async fn get_json_field(
+        &self,
+        name: &str,
+        field_path: &str,
+    ) -> Result<Option<Value>, KvError> {
+        let document = match self.get_json_document(name).await? {
+            Some(document) => document,
+            None => return Ok(None),
+        };
+
+        Ok(get_field(&document, field_path).cloned())
+    }
+
+    /// Sets a single field of the JSON document stored under `name`, addressed by a dot separated
+    /// path such as `"address.city"`. Numeric segments index into arrays like with
+    /// [`KvStore::get_json_field`].
+    ///
+    /// Missing or `null` objects along the path are created, but nothing else is overwritten: a
+    /// path that goes through a string, number or boolean, or indexes past the end of an array,
+    /// fails with [`KvError::InvalidFieldPath`]. The document is written back without its
+    /// previous metadata or expiration.
+    pub async fn set_json_field<T: Serialize>(
+        &self,
+        name: &str,
+        field_path: &str,
+        value: T,
+    ) -> Result<(), KvError> {
+        let mut document = self.get_json_document(name).await?.unwrap_or(Value::Null);
+        set_field(&mut document, field_path, serde_json::to_value(value)?)?;
+        self.put(name, &document)?.execute().await
+    }
+
+    async fn get_json_document(&self, name: &str) -> Result<Option<Value>, KvError> {
+        match self.get(name).text().await? {
+            Some(text) => Ok(Some(serde_json::from_str(&text)?)),
+            None => Ok(None),
+        }
+    }
+}
+
+/// Gets the field of the document at the dot separated path.
+fn get_field<'a>(document: &'a Value, field_path: &str) -> Option<&'a Value> {
+    let pointer: String = field_path
+        .split('.')
+        .map(|segment| format!("/{}", segment.replace('~', "~0").replace('/', "~1")))
+        .collect();
+    document.pointer(&pointer)
+}
+
+/// Sets the field of the document at the dot separated path, creating missing objects.
+fn set_field(document: &mut Value, field_path: &str, value: Value) -> Result<(), KvError> {
+    let mut target = document;
+    for segment in field_path.split('.') {
+        if target.is_null() {
+            *target = Value::Object(Map::new());
+        }
+        target = match target {
+            Value::Object(object) => object.entry(segment).or_insert(Value::Null),
+            Value::Array(array) => {
+                let len = array.len();
+                match array_index(segment).filter(|&index| index < len) {
+                    Some(index) => &mut array[index],
+                    None => {
+                        return Err(KvError::InvalidFieldPath(format!(
+                            "can't set {field_path:?}, {segment:?} is not an index into an array \
+                             of length {len}"
+                        )))
+                    }
+                }
+            }
+            scalar => {
+                let kind = match scalar {
+                    Value::Bool(_) => "boolean",
+                    Value::Number(_) => "number",
+                    _ => "string",
+                };
+                return Err(KvError::InvalidFieldPath(format!(
+                    "can't set {field_path:?}, {segment:?} is a field of a {kind}"
+                )));
+            }
+        };
+    }
+
+    *target = value;
+    Ok(())
+}
+
+/// Parses an array index the same way as [`Value::pointer`], which rejects leading zeros and
+/// signs.
+fn array_index(segment: &str) -> Option<usize> {
+    if segment.starts_with('+') || (segment.len() > 1 && segment.starts_with('0')) {
+        return None;
+    }
+    segment.parse().ok()
+}
+
+fn merge_patch(target: &mut Value, patch: Value) {
+    let patch = match patch {
+        Value::Object(patch) => patch,
+        patch => {
+            *target = patch;
+            return;
+        }
+    };
+
+    if !target.is_object() {
+        *target = Value::Object(Map::new());
+    }
+
+    if let Value::Object(target) = target {
+        for (key, value) in patch {
+            if value.is_null() {
+                target.remove(&key);
+            } else {
+                merge_patch(target.entry(key).or_insert(Value::Null), value);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn gets_fields_of_objects_and_arrays() {
+        let document = json!({ "address": { "city": "Lisbon" }, "items": [1, { "id": 2 }] });
+
+        assert_eq!(get_field(&document, "address.city"), Some(&json!("Lisbon")));
+        assert_eq!(get_field(&document, "items.1.id"), Some(&json!(2)));
+        assert_eq!(get_field(&document, "items.2"), None);
+        assert_eq!(get_field(&document, "items.01"), None);
+        assert_eq!(get_field(&document, "address.zip"), None);
+    }
+
+    #[test]
+    fn gets_fields_with_pointer_characters_in_their_name() {
+        let document = json!({ "a/b": { "c~d": true } });
+
+        assert_eq!(get_field(&document, "a/b.c~d"), Some(&json!(true)));
+    }
+
+    #[test]
+    fn sets_fields_creating_missing_objects() {
+        let mut document = Value::Null;
+        set_field(&mut document, "address.city", json!("Lisbon")).unwrap();
+        set_field(&mut document, "address.zip", json!("1000")).unwrap();
+
+        assert_eq!(
+            document,
+            json!({ "address": { "city": "Lisbon", "zip": "1000" } })
+        );
+    }
+
+    #[test]
+    fn sets_array_elements_without_replacing_the_array() {
+        let mut document = json!({ "items": [1, { "id": 2 }] });
+        set_field(&mut document, "items.0", json!("one")).unwrap();
+        set_field(&mut document, "items.1.id", json!(3)).unwrap();
+
+        assert_eq!(document, json!({ "items": ["one", { "id": 3 }] }));
+    }
+
+    #[test]
+    fn rejects_paths_that_would_overwrite_data() {
+        let original = json!({ "items": [1], "name": "value" });
+
+        for path in [
+            "items.1",
+            "items.01",
+            "items.id",
+            "name.first",
+            "items.0.id",
+        ] {
+            let mut document = original.clone();
+            assert!(
+                matches!(
+                    set_field(&mut document, path, json!(true)),
+                    Err(KvError::InvalidFieldPath(_))
+                ),
+                "{:?} was set",
+                path
+            );
+            assert_eq!(document, original);
+        }
+    }
+}
@@ -0,0 +1,56 @@
+use serde::Serialize;
+use wasm_bindgen::JsValue;
+
+use crate::{KvError, KvStore, PutOptionsBuilder};
+
+/// A builder to configure a put request whose value is only serialized once it is executed,
+/// created by [`KvStore::put_lazy_json`].
+#[must_use = "LazyPutBuilder does nothing until you 'execute' it"]
+pub struct LazyPutBuilder {
+    builder: PutOptionsBuilder,
+    value: Box<dyn erased_serde::Serialize>,
+}
+
+impl KvStore {
+    /// Puts the value into the kv store as JSON like [`KvStore::put`] with a
+    /// [`JsonValue`](crate::JsonValue), but defers serializing it until the put is executed.
+    ///
+    /// This saves the cost of serializing large values for puts that end up being dropped, e.g.
+    /// when a guard condition fails after the builder was created.
+    pub fn put_lazy_json<T: Serialize + 'static>(
+        &self,
+        name: &str,
+        value: T,
+    ) -> Result<LazyPutBuilder, KvError> {
+        crate::validate_key(name)?;
+        Ok(LazyPutBuilder {
+            builder: self.put_raw(name, JsValue::UNDEFINED),
+            value: Box::new(value),
+        })
+    }
+}
+
+impl LazyPutBuilder {
+    /// When (expressed as a [unix timestamp](https://en.wikipedia.org/wiki/Unix_time)) the key
+    /// value pair will expire in the store.
+    pub fn expiration(mut self, expiration: u64) -> Self {
+        self.builder = self.builder.expiration(expiration);
+        self
+    }
+    /// How many seconds until the key value pair will expire.
+    pub fn expiration_ttl(mut self, expiration_ttl: u64) -> Self {
+        self.builder = self.builder.expiration_ttl(expiration_ttl);
+        self
+    }
+    /// Metadata to be stored with the key value pair.
+    pub fn metadata<T: Serialize>(mut self, metadata: T) -> Result<Self, KvError> {
+        self.builder = self.builder.metadata(metadata)?;
+        Ok(self)
+    }
+    /// Serializes the value and puts it in the kv store.
+    pub async fn execute(self) -> Result<(), KvError> {
+        let mut builder = self.builder;
+        builder.value = JsValue::from(serde_json::to_string(&self.value)?);
+        builder.execute().await
+    }
+}
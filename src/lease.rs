@@ -0,0 +1,75 @@
+use js_sys::Date;
+use serde::{Deserialize, Serialize};
+
+use crate::{JsonValue, KvError, KvStore};
+
+/// Ownership of a key claimed with [`KvStore::put_with_lease`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KvLease {
+    /// The name of the leased key.
+    pub name: String,
+    /// The owner holding the lease.
+    pub owner_id: String,
+    /// When the lease was acquired, in milliseconds since the unix epoch.
+    pub acquired_at: u64,
+    /// How many seconds the lease lasts for.
+    pub lease_secs: u64,
+}
+
+impl KvLease {
+    /// Returns `true` if the lease has not expired yet according to the local clock.
+    ///
+    /// KV removes expired keys lazily and the clocks of different workers can drift, so allow
+    /// for some slack instead of relying on this right up to the expiration.
+    pub fn is_valid(&self) -> bool {
+        let expires_at = self
+            .acquired_at
+            .saturating_add(self.lease_secs.saturating_mul(1000));
+        (Date::now() as u64) < expires_at
+    }
+}
+
+/// The metadata stored with a leased key.
+#[derive(Serialize, Deserialize)]
+struct LeaseMetadata<'a> {
+    owner_id: &'a str,
+    acquired_at: u64,
+}
+
+impl KvStore {
+    /// Claims `name` for `owner_id` by putting the value as JSON with an expiration of
+    /// `lease_secs` seconds, which must be at least `60`, and the owner and acquisition time in
+    /// the metadata. Returns `None` if the key already exists, meaning someone else holds the
+    /// lease.
+    ///
+    /// Checking for the key and putting it are separate operations, so two owners claiming the
+    /// same key at the same time can both succeed.
+    pub async fn put_with_lease<T: Serialize>(
+        &self,
+        name: &str,
+        value: &T,
+        lease_secs: u64,
+        owner_id: &str,
+    ) -> Result<Option<KvLease>, KvError> {
+        if self.exists(name).await? {
+            return Ok(None);
+        }
+
+        let acquired_at = Date::now() as u64;
+        self.put(name, JsonValue(value))?
+            .expiration_ttl(lease_secs)
+            .metadata(LeaseMetadata {
+                owner_id,
+                acquired_at,
+            })?
+            .execute()
+            .await?;
+
+        Ok(Some(KvLease {
+            name: name.to_owned(),
+            owner_id: owner_id.to_owned(),
+            acquired_at,
+            lease_secs,
+        }))
+    }
+}
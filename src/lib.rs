@@ -15,17 +15,126 @@
 //! // Get that same metadata.
 //! let (value, metadata) = kv.get("example_key").text_with_metadata::<Vec<usize>>().await?;
 //! ```
+/// Instruments the future of a kv operation with [`instrument::instrument`] when the `tracing`
+/// feature is enabled, and awaits it as is otherwise.
+macro_rules! instrumented {
+    ($operation:literal, $binding:expr, $key:expr, $is_hit:expr, $future:expr) => {{
+        #[cfg(feature = "tracing")]
+        let future = crate::instrument::instrument($operation, $binding, $key, $is_hit, $future);
+        #[cfg(not(feature = "tracing"))]
+        let future = crate::uninstrumented($future);
+        future.await
+    }};
+}
+
+#[forbid(missing_docs)]
+mod audit;
+#[forbid(missing_docs)]
+mod backend;
 #[forbid(missing_docs)]
 mod builder;
+#[forbid(missing_docs)]
+mod bulk;
+#[forbid(missing_docs)]
+mod cache;
+#[forbid(missing_docs)]
+mod chunked;
+#[forbid(missing_docs)]
+mod compress;
+#[forbid(missing_docs)]
+mod conditional;
+#[forbid(missing_docs)]
+mod content;
+#[forbid(missing_docs)]
+mod counter;
+#[forbid(missing_docs)]
+mod diagnostics;
+#[forbid(missing_docs)]
+mod etag;
+#[forbid(missing_docs)]
+mod hex;
+#[forbid(missing_docs)]
+mod inspect;
+#[cfg(feature = "tracing")]
+mod instrument;
+#[forbid(missing_docs)]
+mod json;
+#[forbid(missing_docs)]
+mod lazy;
+#[forbid(missing_docs)]
+mod lease;
+#[forbid(missing_docs)]
+mod migrate;
+#[forbid(missing_docs)]
+mod pagination;
+#[forbid(missing_docs)]
+mod pipeline;
+#[forbid(missing_docs)]
+mod prefix;
+#[forbid(missing_docs)]
+mod sequence;
+#[forbid(missing_docs)]
+mod shadow;
+#[cfg(feature = "signing")]
+#[forbid(missing_docs)]
+mod signing;
+#[forbid(missing_docs)]
+mod sync;
+#[forbid(missing_docs)]
+mod template;
+#[cfg(any(test, feature = "test-utils"))]
+#[forbid(missing_docs)]
+pub mod testing;
+#[forbid(missing_docs)]
+mod tombstone;
+#[forbid(missing_docs)]
+mod versioned;
+#[forbid(missing_docs)]
+mod watch;
 
+pub use audit::*;
+pub use backend::*;
 pub use builder::*;
+pub use bulk::*;
+pub use cache::*;
+pub use conditional::*;
+pub use content::*;
+pub use diagnostics::*;
+pub use lazy::*;
+pub use lease::*;
+pub use migrate::*;
+pub use pagination::*;
+pub use pipeline::*;
+pub use prefix::*;
+pub use sequence::*;
+pub use shadow::*;
+pub use sync::*;
+pub use watch::*;
 
-use js_sys::{global, Function, Object, Promise, Reflect, Uint8Array};
-use serde::{Deserialize, Serialize};
-use serde_json::Value;
-use wasm_bindgen::JsValue;
+#[cfg(feature = "tracing")]
+use std::sync::Arc;
+
+use js_sys::{global, ArrayBuffer, Function, Object, Promise, Reflect, Uint8Array};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::{Map, Value};
+use wasm_bindgen::{JsCast, JsValue};
 use wasm_bindgen_futures::JsFuture;
 
+/// The maximum number of keys a single list request can return.
+const MAX_LIST_LIMIT: u64 = 1000;
+
+/// How many keys are requested per page when listing every key.
+const LIST_PAGE_SIZE: u64 = MAX_LIST_LIMIT;
+
+/// The maximum size of a key in bytes.
+const MAX_KEY_SIZE: usize = 512;
+
+/// The maximum size of a value in bytes.
+const MAX_VALUE_SIZE: usize = 25 * 1024 * 1024;
+
+/// The maximum size of the JSON encoded metadata of a value in bytes.
+const MAX_METADATA_SIZE: usize = 1024;
+
 /// A binding to a Cloudflare KvStore.
 #[derive(Clone)]
 pub struct KvStore {
@@ -35,8 +144,31 @@ pub struct KvStore {
     pub(crate) put_function: Function,
     pub(crate) list_function: Function,
     pub(crate) delete_function: Function,
+    /// The name of the binding, recorded in the tracing spans.
+    #[cfg(feature = "tracing")]
+    pub(crate) binding: Arc<str>,
 }
 
+/// # Safety
+///
+/// The JavaScript handles in a [`KvStore`] are only valid on the thread that created them, which
+/// is what keeps it from being `Send` on its own. A worker runs on a single threaded Wasm
+/// executor, so there is no other thread a store could be moved to or shared with. This is what
+/// lets a store be kept in state that frameworks require to be `Send + Sync`, e.g. an
+/// `axum::Extension`.
+///
+/// The impls only exist for `wasm32` without the `atomics` target feature, the only targets where
+/// that holds. Everything else in the store, such as the binding name recorded for tracing, is
+/// `Send + Sync` by itself.
+#[cfg(all(target_arch = "wasm32", not(target_feature = "atomics")))]
+unsafe impl Send for KvStore {}
+
+/// # Safety
+///
+/// See the `Send` impl above, a single thread can't access a store concurrently.
+#[cfg(all(target_arch = "wasm32", not(target_feature = "atomics")))]
+unsafe impl Sync for KvStore {}
+
 impl KvStore {
     /// Creates a new [`KvStore`] with the binding specified in your `wrangler.toml`.
     pub fn create(binding: &str) -> Result<Self, KvError> {
@@ -53,6 +185,8 @@ impl KvStore {
                 list_function: get(&this, "list")?.into(),
                 delete_function: get(&this, "delete")?.into(),
                 this: this.into(),
+                #[cfg(feature = "tracing")]
+                binding: binding.into(),
             })
         }
     }
@@ -73,10 +207,24 @@ impl KvStore {
                 list_function: get(&this, "list")?.into(),
                 delete_function: get(&this, "delete")?.into(),
                 this: this.into(),
+                #[cfg(feature = "tracing")]
+                binding: binding.into(),
             })
         }
     }
 
+    /// Checks whether a kv store is bound under `binding`, i.e. the global has an object by that
+    /// name with a `get` function, so optional bindings can be detected without handling the
+    /// error from [`KvStore::create`].
+    pub fn namespace_exists(binding: &str) -> bool {
+        let this = match get(&global(), binding) {
+            Ok(this) if this.is_object() => this,
+            _ => return false,
+        };
+
+        matches!(get(&this, "get"), Ok(get) if get.is_function())
+    }
+
     /// Fetches the value from the kv store by name.
     pub fn get(&self, name: &str) -> GetOptionsBuilder {
         GetOptionsBuilder {
@@ -86,28 +234,74 @@ impl KvStore {
             name: JsValue::from(name),
             cache_ttl: None,
             value_type: None,
+            #[cfg(feature = "tracing")]
+            binding: self.binding.clone(),
         }
     }
 
+    /// Fetches the raw value from the kv store by name, letting the edge location it is read from
+    /// cache it for `cache_ttl_secs` seconds. See [`GetOptionsBuilder::cache_ttl`] for the
+    /// trade-offs.
+    pub async fn get_with_cache_ttl(
+        &self,
+        name: &str,
+        cache_ttl_secs: u32,
+    ) -> Result<Option<KvValue>, KvError> {
+        self.get(name)
+            .cache_ttl(u64::from(cache_ttl_secs))
+            .value()
+            .await
+    }
+
+    /// Fetches the value from the kv store by name, configured with the returned builder and
+    /// fetched with [`GetOptionsBuilder::execute`] or
+    /// [`GetOptionsBuilder::execute_with_metadata`].
+    ///
+    /// This is the same builder as returned by [`KvStore::get`], under a name that mirrors the
+    /// put side.
+    pub fn get_with_options(&self, name: &str) -> GetOptionsBuilder {
+        self.get(name)
+    }
+
+    /// Fetches the value from the kv store by name as raw bytes, reading it as an `ArrayBuffer` so
+    /// binary values don't go through a text conversion.
+    pub async fn get_bytes(&self, name: &str) -> Result<Option<Vec<u8>>, KvError> {
+        self.get(name).bytes().await
+    }
+
+    /// Fetches the value from the kv store by name and deserializes it from JSON with
+    /// `serde_json`, reporting malformed values as [`KvError::Serialization`].
+    pub async fn get_json<T: DeserializeOwned>(&self, name: &str) -> Result<Option<T>, KvError> {
+        match self.get(name).text().await? {
+            Some(text) => Ok(Some(serde_json::from_str(&text)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Puts the value into the kv store serialized as JSON with `serde_json`.
+    pub fn put_json<T: Serialize>(
+        &self,
+        name: &str,
+        value: &T,
+    ) -> Result<PutOptionsBuilder, KvError> {
+        self.put(name, serde_json::to_string(value)?)
+    }
+
     /// Puts data into the kv store.
     pub fn put<T: ToRawKvValue>(&self, name: &str, value: T) -> Result<PutOptionsBuilder, KvError> {
-        Ok(PutOptionsBuilder {
-            this: self.this.clone(),
-            put_function: self.put_function.clone(),
-            name: JsValue::from(name),
-            value: value.raw_kv_value()?,
-            expiration: None,
-            expiration_ttl: None,
-            metadata: None,
-        })
+        validate_key(name)?;
+        Ok(self.put_raw(name, value.raw_kv_value()?))
     }
 
     /// Puts the specified byte slice into the kv store.
     pub fn put_bytes(&self, name: &str, value: &[u8]) -> Result<PutOptionsBuilder, KvError> {
-        let typed_array = Uint8Array::new_with_length(value.len() as u32);
-        typed_array.copy_from(value);
-        let value: JsValue = typed_array.buffer().into();
-        Ok(PutOptionsBuilder {
+        validate_key(name)?;
+        Ok(self.put_raw(name, Uint8Array::from(value).buffer().into()))
+    }
+
+    /// Creates a put builder for a value that has already been converted for the kv store.
+    pub(crate) fn put_raw(&self, name: &str, value: JsValue) -> PutOptionsBuilder {
+        PutOptionsBuilder {
             this: self.this.clone(),
             put_function: self.put_function.clone(),
             name: JsValue::from(name),
@@ -115,7 +309,37 @@ impl KvStore {
             expiration: None,
             expiration_ttl: None,
             metadata: None,
-        })
+            reserved_metadata: Map::new(),
+            #[cfg(feature = "tracing")]
+            binding: self.binding.clone(),
+        }
+    }
+
+    /// Gets a best-effort hint of how many bytes the value stored under `name` takes up, or `None`
+    /// if the key doesn't exist.
+    ///
+    /// If the metadata of the key has a numeric `__size` field it is trusted as is, otherwise the
+    /// value is fetched as an `ArrayBuffer` and only its length is read, so the value is never
+    /// copied into wasm memory. It does however still count as a full read of the value, and a
+    /// stale `__size` field will be returned as is.
+    pub async fn key_size_hint(&self, name: &str) -> Result<Option<u64>, KvError> {
+        let (value, metadata) = self
+            .get(name)
+            .type_hint(KvValueType::ArrayBuffer)
+            .get_with_metadata::<Value>()
+            .await?;
+
+        let size = metadata
+            .as_ref()
+            .and_then(|metadata| metadata.get("__size"))
+            .and_then(Value::as_u64);
+        if size.is_some() {
+            return Ok(size);
+        }
+
+        Ok(value
+            .dyn_ref::<ArrayBuffer>()
+            .map(|buffer| buffer.byte_length() as u64))
     }
 
     /// Lists the keys in the kv store.
@@ -126,15 +350,167 @@ impl KvStore {
             limit: None,
             cursor: None,
             prefix: None,
+            metadata: false,
+            #[cfg(feature = "tracing")]
+            binding: self.binding.clone(),
         }
     }
 
+    /// Lists every key in the kv store, following the cursor from page to page until the listing
+    /// is complete.
+    ///
+    /// All of the keys are held in memory at once, see [`KvStore::keys_stream`] to process them
+    /// page by page instead.
+    pub async fn list_all(&self) -> Result<Vec<Key>, KvError> {
+        self.list_keys(None).await
+    }
+
+    /// Like [`KvStore::list_all`], but only lists the keys starting with `prefix`.
+    pub async fn list_all_with_prefix(&self, prefix: &str) -> Result<Vec<Key>, KvError> {
+        self.list_keys(Some(prefix)).await
+    }
+
+    /// Lists all keys in the kv store, optionally restricted to those starting with `prefix`, and
+    /// deserializes their metadata to `M`.
+    ///
+    /// Keys whose metadata fails to deserialize don't fail the whole call, they are returned
+    /// separately along with the error so that they can be logged.
+    #[allow(clippy::type_complexity)]
+    pub async fn list_all_with_typed_metadata<M: DeserializeOwned>(
+        &self,
+        prefix: Option<&str>,
+    ) -> Result<(Vec<TypedKey<M>>, Vec<(Key, serde_json::Error)>), KvError> {
+        let mut keys = Vec::new();
+        let mut malformed = Vec::new();
+
+        for key in self.list_keys(prefix).await? {
+            match key.metadata_typed::<M>() {
+                Ok(metadata) => keys.push(TypedKey {
+                    name: key.name,
+                    expiration: key.expiration,
+                    metadata,
+                }),
+                Err(e) => malformed.push((key, e)),
+            }
+        }
+
+        Ok((keys, malformed))
+    }
+
+    /// Renews the expiration of a key to `ttl` seconds from now by putting its value back with
+    /// the same metadata. Returns `false` if the key doesn't exist.
+    pub async fn touch(&self, name: &str, ttl: u64) -> Result<bool, KvError> {
+        self.copy_entry_expiring(name, self, name, Some(ttl)).await
+    }
+
     /// Deletes a key in the kv store.
     pub async fn delete(&self, name: &str) -> Result<(), KvError> {
-        let name = JsValue::from(name);
-        let promise: Promise = self.delete_function.call1(&self.this, &name)?.into();
-        JsFuture::from(promise).await?;
-        Ok(())
+        instrumented!(
+            "delete",
+            self.binding.clone(),
+            Some(name.to_owned()),
+            |_| None,
+            async {
+                validate_key(name)?;
+                let name = JsValue::from(name);
+                let promise: Promise = self.delete_function.call1(&self.this, &name)?.into();
+                JsFuture::from(promise).await?;
+                Ok(())
+            }
+        )
+    }
+
+    /// Checks whether a value is stored under `name`.
+    ///
+    /// This is a regular read, which KV bills at a fraction of a list request, but the value is
+    /// fetched as an `ArrayBuffer` and never copied into wasm memory or decoded.
+    pub async fn exists(&self, name: &str) -> Result<bool, KvError> {
+        let value = self
+            .get(name)
+            .type_hint(KvValueType::ArrayBuffer)
+            .get()
+            .await?;
+        Ok(!value.is_null() && !value.is_undefined())
+    }
+
+    /// Finds the listing entry of the key `name`, which includes its expiration. Since keys are
+    /// listed in lexicographic order, the key itself is always the first one starting with `name`.
+    pub(crate) async fn find_key(&self, name: &str) -> Result<Option<Key>, KvError> {
+        let response = self.list().prefix(name.into()).limit(1).execute().await?;
+        Ok(response.keys.into_iter().find(|key| key.name == name))
+    }
+
+    /// Lists every key in the kv store, following the cursor until the listing is complete.
+    pub(crate) async fn list_keys(&self, prefix: Option<&str>) -> Result<Vec<Key>, KvError> {
+        self.list_keys_limited(prefix, None).await
+    }
+
+    /// Like [`KvStore::list_keys`], but stops once `limit` keys have been listed.
+    pub(crate) async fn list_keys_limited(
+        &self,
+        prefix: Option<&str>,
+        limit: Option<u64>,
+    ) -> Result<Vec<Key>, KvError> {
+        let mut keys = Vec::new();
+        let mut cursor = None;
+
+        loop {
+            let mut builder = self.list();
+            if let Some(prefix) = prefix {
+                builder = builder.prefix(prefix.into());
+            }
+            if let Some(cursor) = cursor {
+                builder = builder.cursor(cursor);
+            }
+            let page_size = match limit {
+                Some(limit) => limit.saturating_sub(keys.len() as u64).min(LIST_PAGE_SIZE),
+                None => LIST_PAGE_SIZE,
+            };
+            if page_size == 0 {
+                return Ok(keys);
+            }
+            builder = builder.limit(page_size);
+
+            let mut page = builder.execute().await?;
+            keys.append(&mut page.keys);
+
+            match page.cursor {
+                Some(next) if !page.list_complete => cursor = Some(next),
+                _ => return Ok(keys),
+            }
+        }
+    }
+
+    /// Copies the value and metadata stored under `name` into `target` as `new_name`, returning
+    /// `false` if there was no value to copy. The expiration of the key is not carried over.
+    pub(crate) async fn copy_entry(
+        &self,
+        name: &str,
+        target: &KvStore,
+        new_name: &str,
+    ) -> Result<bool, KvError> {
+        self.copy_entry_expiring(name, target, new_name, None).await
+    }
+
+    /// Like [`KvStore::copy_entry`], but the copy expires after `expiration_ttl` seconds if set.
+    pub(crate) async fn copy_entry_expiring(
+        &self,
+        name: &str,
+        target: &KvStore,
+        new_name: &str,
+        expiration_ttl: Option<u64>,
+    ) -> Result<bool, KvError> {
+        let (value, metadata) = self.get(name).bytes_with_metadata::<Value>().await?;
+        let value = match value {
+            Some(value) => value,
+            None => return Ok(false),
+        };
+
+        let mut builder = target.put_bytes(new_name, &value)?;
+        builder.metadata = metadata;
+        builder.expiration_ttl = expiration_ttl;
+        builder.execute().await?;
+        Ok(true)
     }
 }
 
@@ -161,15 +537,146 @@ pub struct Key {
     pub metadata: Option<Value>,
 }
 
+impl Key {
+    /// Tries to deserialize the metadata of the key to the generic type.
+    pub fn metadata_typed<M: DeserializeOwned>(&self) -> Result<Option<M>, serde_json::Error> {
+        self.metadata.as_ref().map(M::deserialize).transpose()
+    }
+}
+
+/// The representation of a key in the KV store with its metadata deserialized.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TypedKey<M> {
+    /// The name of the key.
+    pub name: String,
+    /// When (expressed as a [unix timestamp](https://en.wikipedia.org/wiki/Unix_time)) the key
+    /// value pair will expire in the store.
+    pub expiration: Option<u64>,
+    /// The metadata associated with the key.
+    pub metadata: Option<M>,
+}
+
+/// A raw value fetched from the kv store.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KvValue(pub(crate) Vec<u8>);
+
+impl KvValue {
+    /// Gets the value as a string, replacing any invalid UTF-8 sequences.
+    pub fn as_string(&self) -> String {
+        String::from_utf8_lossy(&self.0).into_owned()
+    }
+
+    /// Tries to deserialize the value as JSON to the generic type.
+    pub fn as_json<T: DeserializeOwned>(&self) -> Result<T, KvError> {
+        Ok(serde_json::from_slice(&self.0)?)
+    }
+
+    /// Gets the value as a byte slice.
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.0
+    }
+
+    /// Converts the value into its raw bytes.
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.0
+    }
+}
+
 /// A simple error type that can occur during kv operations.
 #[derive(Debug, thiserror::Error)]
 pub enum KvError {
-    #[error("js error: {0:?}")]
+    #[error("KV JavaScript error: {}", js_error_message(.0))]
     JavaScript(JsValue),
-    #[error("unable to serialize/deserialize: {0}")]
-    Serialization(serde_json::Error),
-    #[error("invalid kv store: {0}")]
+    #[error("KV serialization error: {0}")]
+    Serialization(#[source] serde_json::Error),
+    #[error("KV store not found: {0}")]
     InvalidKvStore(String),
+    #[error("invalid metadata: {0}")]
+    InvalidMetadata(String),
+    #[error("KV compression error: {0}")]
+    Compression(#[source] std::io::Error),
+    #[error("signature invalid")]
+    SignatureInvalid,
+    #[error("negatively cached: {0}")]
+    NegativelyCached(String),
+    #[error("invalid page token: {0}")]
+    InvalidPageToken(String),
+    #[error("invalid key: {0}")]
+    InvalidKey(String),
+    #[error("invalid field path: {0}")]
+    InvalidFieldPath(String),
+    #[error(
+        "value too large: {actual_bytes} bytes, the maximum is {max} bytes",
+        max = MAX_VALUE_SIZE
+    )]
+    ValueTooLarge {
+        /// The size of the value.
+        actual_bytes: usize,
+    },
+    #[error(
+        "metadata too large: {actual_bytes} bytes as JSON, the maximum is {max} bytes",
+        max = MAX_METADATA_SIZE
+    )]
+    MetadataTooLarge {
+        /// The size of the JSON encoded metadata.
+        actual_bytes: usize,
+    },
+}
+
+/// Checks that `name` can be used as a key, which KV requires to be non-empty, at most 512 bytes
+/// long and neither `.` nor `..`.
+///
+/// This is done by the operations of a [`KvStore`] before calling into the runtime, but can also
+/// be used to reject user provided keys up front.
+pub fn validate_key(name: &str) -> Result<(), KvError> {
+    if name.is_empty() {
+        return Err(KvError::InvalidKey("keys can't be empty".into()));
+    }
+    if name == "." || name == ".." {
+        return Err(KvError::InvalidKey(format!(
+            "{name:?} is not allowed as a key"
+        )));
+    }
+    if name.len() > MAX_KEY_SIZE {
+        return Err(KvError::InvalidKey(format!(
+            "key is {} bytes long, the maximum is {MAX_KEY_SIZE} bytes",
+            name.len()
+        )));
+    }
+    Ok(())
+}
+
+/// A readable representation of a thrown JavaScript value, using the message of `Error`s and
+/// falling back to the debug representation for values that aren't strings.
+fn js_error_message(value: &JsValue) -> String {
+    if let Some(error) = value.dyn_ref::<js_sys::Error>() {
+        return error.to_string().into();
+    }
+
+    value.as_string().unwrap_or_else(|| format!("{value:?}"))
+}
+
+/// Checks that a string or `ArrayBuffer` value fits in the kv store. Other values, such as
+/// streams, don't have a size known up front and are left for the runtime to check.
+fn check_value_size(value: &JsValue) -> Result<(), KvError> {
+    let actual_bytes = if let Some(buffer) = value.dyn_ref::<ArrayBuffer>() {
+        buffer.byte_length() as usize
+    } else if let Some(string) = value.dyn_ref::<js_sys::JsString>() {
+        // A UTF-16 code unit takes at most 3 bytes as UTF-8, so only strings that could be too
+        // large are copied out to count their bytes.
+        if string.length() as usize * 3 <= MAX_VALUE_SIZE {
+            return Ok(());
+        }
+        String::from(string).len()
+    } else {
+        return Ok(());
+    };
+
+    if actual_bytes > MAX_VALUE_SIZE {
+        Err(KvError::ValueTooLarge { actual_bytes })
+    } else {
+        Ok(())
+    }
 }
 
 impl From<KvError> for JsValue {
@@ -180,6 +687,27 @@ impl From<KvError> for JsValue {
             KvError::InvalidKvStore(binding) => {
                 format!("KvError::InvalidKvStore: {binding}").into()
             }
+            KvError::InvalidMetadata(reason) => {
+                format!("KvError::InvalidMetadata: {reason}").into()
+            }
+            KvError::Compression(e) => format!("KvError::Compression: {e}").into(),
+            KvError::SignatureInvalid => "KvError::SignatureInvalid".into(),
+            KvError::NegativelyCached(reason) => {
+                format!("KvError::NegativelyCached: {reason}").into()
+            }
+            KvError::InvalidPageToken(token) => {
+                format!("KvError::InvalidPageToken: {token}").into()
+            }
+            KvError::InvalidKey(reason) => format!("KvError::InvalidKey: {reason}").into(),
+            KvError::InvalidFieldPath(reason) => {
+                format!("KvError::InvalidFieldPath: {reason}").into()
+            }
+            KvError::ValueTooLarge { actual_bytes } => {
+                format!("KvError::ValueTooLarge: {actual_bytes} bytes").into()
+            }
+            KvError::MetadataTooLarge { actual_bytes } => {
+                format!("KvError::MetadataTooLarge: {actual_bytes} bytes").into()
+            }
         }
     }
 }
@@ -197,7 +725,11 @@ impl From<serde_json::Error> for KvError {
 }
 
 /// A trait for things that can be converted to [`wasm_bindgen::JsValue`] to be passed to the kv.
+///
+/// Strings are stored as is and bytes as an `ArrayBuffer`, to store any other serializable type as
+/// JSON wrap it in a [`JsonValue`].
 pub trait ToRawKvValue {
+    /// Converts the value to the representation stored in the kv.
     fn raw_kv_value(&self) -> Result<JsValue, KvError>;
 }
 
@@ -207,24 +739,71 @@ impl ToRawKvValue for str {
     }
 }
 
-impl<T: Serialize> ToRawKvValue for T {
+impl ToRawKvValue for String {
     fn raw_kv_value(&self) -> Result<JsValue, KvError> {
-        let value = serde_wasm_bindgen::to_value(self).map_err(JsValue::from)?;
-
-        if value.as_string().is_some() {
-            Ok(value)
-        } else if let Some(number) = value.as_f64() {
-            Ok(JsValue::from(number.to_string()))
-        } else if let Some(boolean) = value.as_bool() {
-            Ok(JsValue::from(boolean.to_string()))
-        } else {
-            js_sys::JSON::stringify(&value)
-                .map(JsValue::from)
-                .map_err(Into::into)
-        }
+        self.as_str().raw_kv_value()
     }
 }
 
+impl ToRawKvValue for [u8] {
+    fn raw_kv_value(&self) -> Result<JsValue, KvError> {
+        Ok(Uint8Array::from(self).buffer().into())
+    }
+}
+
+impl ToRawKvValue for Vec<u8> {
+    fn raw_kv_value(&self) -> Result<JsValue, KvError> {
+        self.as_slice().raw_kv_value()
+    }
+}
+
+impl ToRawKvValue for Value {
+    fn raw_kv_value(&self) -> Result<JsValue, KvError> {
+        Ok(JsValue::from(serde_json::to_string(self)?))
+    }
+}
+
+impl<T: ToRawKvValue + ?Sized> ToRawKvValue for &T {
+    fn raw_kv_value(&self) -> Result<JsValue, KvError> {
+        (**self).raw_kv_value()
+    }
+}
+
+/// A wrapper to store any serializable value in the kv as JSON.
+///
+/// ```ignore
+/// kv.put("example_key", JsonValue(&my_struct))?.execute().await?;
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct JsonValue<T: Serialize>(pub T);
+
+impl<T: Serialize> ToRawKvValue for JsonValue<T> {
+    fn raw_kv_value(&self) -> Result<JsValue, KvError> {
+        Ok(JsValue::from(serde_json::to_string(&self.0)?))
+    }
+}
+
+/// Passes the future through unchanged, only so the output of async blocks given to
+/// `instrumented!` is inferred as it is with the `tracing` feature enabled.
+#[cfg(not(feature = "tracing"))]
+fn uninstrumented<T, Fut>(future: Fut) -> Fut
+where
+    Fut: std::future::Future<Output = Result<T, KvError>>,
+{
+    future
+}
+
+/// Converts a value to a plain JavaScript value, serializing maps as objects since the kv does not
+/// accept the `Map`s `serde_wasm_bindgen` produces by default.
+fn to_js_value<T: Serialize + ?Sized>(value: &T) -> Result<JsValue, KvError> {
+    let serializer = serde_wasm_bindgen::Serializer::json_compatible();
+    Ok(value.serialize(&serializer).map_err(JsValue::from)?)
+}
+
 fn get(target: &JsValue, name: &str) -> Result<JsValue, JsValue> {
     Reflect::get(target, &JsValue::from(name))
 }
+
+fn set(target: &JsValue, name: &str, value: &JsValue) -> Result<bool, JsValue> {
+    Reflect::set(target, &JsValue::from(name), value)
+}
@@ -0,0 +1,229 @@
+use js_sys::Date;
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::{KvError, KvStore};
+
+/// The metadata field holding when an archived key was deleted, in milliseconds since the unix
+/// epoch.
+const ARCHIVED_AT_FIELD: &str = "__archived_at";
+
+/// The outcome of a [`KvStore::rotate_key`] call.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RotationResult {
+    /// If there was a value under the old name to rotate.
+    pub old_existed: bool,
+    /// If the value was written under the new name.
+    pub new_written: bool,
+    /// If the old key was deleted. This is `false` when `delete_old` wasn't set, and also when
+    /// deleting failed after the new key was written, in which case the old key is still there to
+    /// be deleted again.
+    pub old_deleted: bool,
+}
+
+/// The outcome of a [`KvStore::migrate_serialization`] call.
+#[derive(Debug, Default)]
+pub struct MigrationResult {
+    /// The number of keys that were rewritten in the new format.
+    pub migrated: u64,
+    /// The number of keys that already were in the new format.
+    pub unchanged: u64,
+    /// The keys that could not be decoded or rewritten along with the reason.
+    pub failed: Vec<(String, KvError)>,
+}
+
+impl KvStore {
+    /// Renames every key, optionally restricted to those starting with `prefix`, to the name
+    /// returned by `f`, returning the number of renamed keys. Keys that `f` maps to their own name
+    /// are left alone.
+    ///
+    /// Each value is copied along with its metadata before the old key is deleted, so a partial
+    /// failure never loses data. Expirations are not carried over. Use
+    /// [`KvStore::map_keys_dry_run`] to check the mapping first.
+    pub async fn map_keys<F: Fn(&str) -> String>(
+        &self,
+        prefix: Option<&str>,
+        f: F,
+    ) -> Result<u64, KvError> {
+        let mut renamed = 0;
+
+        for (old_name, new_name) in self.map_keys_dry_run(prefix, f).await? {
+            if self.copy_entry(&old_name, self, &new_name).await? {
+                self.delete(&old_name).await?;
+                renamed += 1;
+            }
+        }
+
+        Ok(renamed)
+    }
+
+    /// Returns the `(old_name, new_name)` pairs [`KvStore::map_keys`] would rename without
+    /// changing anything.
+    pub async fn map_keys_dry_run<F: Fn(&str) -> String>(
+        &self,
+        prefix: Option<&str>,
+        f: F,
+    ) -> Result<Vec<(String, String)>, KvError> {
+        Ok(self
+            .list_keys(prefix)
+            .await?
+            .into_iter()
+            .filter_map(|key| {
+                let new_name = f(&key.name);
+                (new_name != key.name).then_some((key.name, new_name))
+            })
+            .collect())
+    }
+
+    /// Copies every key starting with `source_prefix` into `target` under the name returned by
+    /// `transform`, e.g. to migrate `users/alice/profile` to `alice_profile`. Returns the number
+    /// of migrated keys.
+    ///
+    /// Values are copied along with their metadata but without their expiration. The source keys
+    /// are kept, so the migration can be reverted, unless `delete_source` is set.
+    pub async fn flatten_to(
+        &self,
+        source_prefix: &str,
+        target: &KvStore,
+        transform: impl Fn(&str) -> String,
+        delete_source: bool,
+    ) -> Result<u64, KvError> {
+        let mut migrated = 0;
+
+        for key in self.list_keys(Some(source_prefix)).await? {
+            if !self
+                .copy_entry(&key.name, target, &transform(&key.name))
+                .await?
+            {
+                continue;
+            }
+            if delete_source {
+                self.delete(&key.name).await?;
+            }
+            migrated += 1;
+        }
+
+        Ok(migrated)
+    }
+
+    /// Rewrites every value, optionally restricted to the keys starting with `prefix`, from an old
+    /// encoding to JSON as stored by [`JsonValue`](crate::JsonValue). Each value is decoded from
+    /// its text with `old_deserializer` and only written back if its new encoding differs from
+    /// the stored one.
+    ///
+    /// `old_deserializer` is called once per key since a serde deserializer can only be used
+    /// once, e.g. `|text| serde_json::from_str::<Legacy>(text).map(Config::from)`. Metadata is
+    /// kept, as are expirations that have not passed yet. A key that fails doesn't stop the
+    /// others, it is reported in [`MigrationResult::failed`].
+    pub async fn migrate_serialization<T, E, F>(
+        &self,
+        prefix: Option<&str>,
+        old_deserializer: F,
+    ) -> Result<MigrationResult, KvError>
+    where
+        T: Serialize,
+        E: Into<KvError>,
+        F: Fn(&str) -> Result<T, E>,
+    {
+        let now = (Date::now() / 1000.0) as u64;
+        let mut result = MigrationResult::default();
+
+        for key in self.list_keys(prefix).await? {
+            let migrated = async {
+                let (text, metadata) = self.get(&key.name).text_with_metadata::<Value>().await?;
+                let text = match text {
+                    Some(text) => text,
+                    None => return Ok(false),
+                };
+
+                let value = old_deserializer(&text).map_err(Into::into)?;
+                let new_text = serde_json::to_string(&value)?;
+                if new_text == text {
+                    return Ok(false);
+                }
+
+                let mut builder = self.put(&key.name, new_text)?;
+                builder.metadata = metadata;
+                builder.expiration = key.expiration.filter(|expiration| *expiration > now);
+                builder.execute().await?;
+                Ok::<_, KvError>(true)
+            };
+
+            let migrated = migrated.await;
+            match migrated {
+                Ok(true) => result.migrated += 1,
+                Ok(false) => result.unchanged += 1,
+                Err(e) => result.failed.push((key.name, e)),
+            }
+        }
+
+        Ok(result)
+    }
+
+    /// Moves the value and metadata stored under `name` into `archive` under the same key, adding
+    /// the deletion time as `__archived_at` to the metadata, then deletes it from this store. The
+    /// archived copy expires after `archive_ttl_secs` seconds. Returns `false` if the key was not
+    /// present.
+    ///
+    /// The key is only deleted once the archived copy was written, fails with
+    /// [`KvError::InvalidMetadata`] if the stored metadata is not an object.
+    pub async fn delete_and_archive(
+        &self,
+        name: &str,
+        archive: &KvStore,
+        archive_ttl_secs: u64,
+    ) -> Result<bool, KvError> {
+        let (value, metadata) = self.get(name).bytes_with_metadata::<Value>().await?;
+        let value = match value {
+            Some(value) => value,
+            None => return Ok(false),
+        };
+
+        let mut builder = archive
+            .put_bytes(name, &value)?
+            .expiration_ttl(archive_ttl_secs)
+            .reserved_metadata(ARCHIVED_AT_FIELD, (Date::now() as u64).into());
+        builder.metadata = metadata;
+        builder.execute().await?;
+
+        self.delete(name).await?;
+        Ok(true)
+    }
+
+    /// Copies the value and metadata stored under `old_name` to `new_name`, expiring after
+    /// `new_expiration_ttl` seconds if set, so both keys coexist while configuration is rotated.
+    /// The old key is only deleted once `delete_old` is set.
+    ///
+    /// Failing to read the old key or to write the new one is returned as an error, since nothing
+    /// has changed at that point. Failing to delete the old key is reported through
+    /// [`RotationResult::old_deleted`] instead, so the caller still learns that the new key was
+    /// written.
+    pub async fn rotate_key(
+        &self,
+        old_name: &str,
+        new_name: &str,
+        new_expiration_ttl: Option<u64>,
+        delete_old: bool,
+    ) -> Result<RotationResult, KvError> {
+        let mut result = RotationResult::default();
+
+        let (value, metadata) = self.get(old_name).bytes_with_metadata::<Value>().await?;
+        let value = match value {
+            Some(value) => value,
+            None => return Ok(result),
+        };
+        result.old_existed = true;
+
+        let mut builder = self.put_bytes(new_name, &value)?;
+        builder.metadata = metadata;
+        builder.expiration_ttl = new_expiration_ttl;
+        builder.execute().await?;
+        result.new_written = true;
+
+        if delete_old {
+            result.old_deleted = self.delete(old_name).await.is_ok();
+        }
+
+        Ok(result)
+    }
+}
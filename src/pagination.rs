@@ -0,0 +1,301 @@
+use std::{fmt, future::Future, vec};
+
+use futures_util::stream::{self, Stream};
+use serde::{Deserialize, Serialize};
+
+use crate::{hex, Key, KvError, KvStore, ListOptions, MAX_LIST_LIMIT};
+
+/// An opaque cursor pointing to the next page of a listing, which can be passed to clients e.g.
+/// as a query parameter and handed back to [`KvStore::list_by_page`] later.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct KvCursor(String);
+
+impl KvCursor {
+    /// The cursor as returned by the kv store.
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl From<String> for KvCursor {
+    fn from(cursor: String) -> Self {
+        Self(cursor)
+    }
+}
+
+impl fmt::Display for KvCursor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+/// A single page of keys returned by [`KvStore::list_by_page`].
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ListPage {
+    /// The keys on this page.
+    pub keys: Vec<Key>,
+    /// The cursor to fetch the next page with, `None` on the last page.
+    pub next_cursor: Option<KvCursor>,
+    /// If this is the last page of the listing.
+    pub is_last_page: bool,
+}
+
+/// A single page of keys returned by [`KvStore::list_cursor_page_streaming`].
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ListPageResponse {
+    /// The keys on this page.
+    pub keys: Vec<Key>,
+    /// The encoded [`PageToken`] to fetch the next page with, `None` on the last page.
+    pub next_cursor: Option<String>,
+    /// How many keys have been listed so far, including this page.
+    pub total_so_far: u64,
+    /// If this is the last page of the listing.
+    pub is_complete: bool,
+}
+
+/// A cursor along with the number of keys listed before it, encoded as a URL safe string so it
+/// can be handed to clients and back to [`KvStore::list_cursor_page_streaming`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct PageToken {
+    /// The cursor pointing to the next page.
+    pub cursor: KvCursor,
+    /// How many keys were listed before the cursor.
+    pub total_so_far: u64,
+}
+
+impl PageToken {
+    /// Encodes the token as a hex string.
+    pub fn encode(&self) -> String {
+        hex::encode(format!("{}:{}", self.total_so_far, self.cursor).as_bytes())
+    }
+
+    /// Decodes a token created with [`PageToken::encode`].
+    pub fn decode(token: &str) -> Result<Self, KvError> {
+        let invalid = || KvError::InvalidPageToken(token.to_owned());
+
+        let bytes = hex::decode(token).ok_or_else(invalid)?;
+        let decoded = String::from_utf8(bytes).map_err(|_| invalid())?;
+
+        let (total_so_far, cursor) = decoded.split_once(':').ok_or_else(invalid)?;
+        Ok(Self {
+            cursor: KvCursor(cursor.to_owned()),
+            total_so_far: total_so_far.parse().map_err(|_| invalid())?,
+        })
+    }
+}
+
+impl KvStore {
+    /// Lists exactly one page of keys, starting at `cursor` if given, which takes precedence over
+    /// the cursor in `opts`.
+    ///
+    /// This is meant for spreading a listing across requests, such as a paginated API endpoint
+    /// handing the cursor to its clients.
+    pub async fn list_by_page(
+        &self,
+        opts: ListOptions,
+        cursor: Option<KvCursor>,
+    ) -> Result<ListPage, KvError> {
+        let mut builder = self.list().from_options(opts);
+        if let Some(cursor) = cursor {
+            builder = builder.cursor(cursor.0);
+        }
+
+        let response = builder.execute().await?;
+        let next_cursor = match response.cursor {
+            Some(cursor) if !response.list_complete && !cursor.is_empty() => Some(KvCursor(cursor)),
+            _ => None,
+        };
+
+        Ok(ListPage {
+            keys: response.keys,
+            is_last_page: next_cursor.is_none(),
+            next_cursor,
+        })
+    }
+
+    /// Like [`KvStore::list_by_page`], but the cursor is an encoded [`PageToken`] that also keeps
+    /// a running total of the listed keys, for "showing items X-Y of N" style pagination without
+    /// counting every key upfront.
+    ///
+    /// Pass `None` to start from the first page and the returned
+    /// [`ListPageResponse::next_cursor`] to continue.
+    pub async fn list_cursor_page_streaming(
+        &self,
+        opts: ListOptions,
+        cursor: Option<&str>,
+    ) -> Result<ListPageResponse, KvError> {
+        let token = cursor.map(PageToken::decode).transpose()?;
+        let total_before = token.as_ref().map_or(0, |token| token.total_so_far);
+
+        let page = self
+            .list_by_page(opts, token.map(|token| token.cursor))
+            .await?;
+        let total_so_far = total_before + page.keys.len() as u64;
+        let next_cursor = page.next_cursor.map(|cursor| {
+            PageToken {
+                cursor,
+                total_so_far,
+            }
+            .encode()
+        });
+
+        Ok(ListPageResponse {
+            keys: page.keys,
+            next_cursor,
+            total_so_far,
+            is_complete: page.is_last_page,
+        })
+    }
+
+    /// Lists the keys starting with `prefix` one page of up to `chunk_size` keys at a time, capped
+    /// at the 1000 keys a single list request can return, and passes every page to `f`, returning
+    /// the total number of keys.
+    ///
+    /// The next page is only fetched once `f` has finished with the current one, so only a single
+    /// page is held in memory at a time. An error returned by `f` stops the scan.
+    pub async fn scan_prefix_chunked<F, Fut>(
+        &self,
+        prefix: &str,
+        chunk_size: u64,
+        mut f: F,
+    ) -> Result<u64, KvError>
+    where
+        F: FnMut(Vec<Key>) -> Fut,
+        Fut: Future<Output = Result<(), KvError>>,
+    {
+        let mut cursor = None;
+        let mut total = 0;
+
+        loop {
+            let options = ListOptions {
+                limit: Some(chunk_size.clamp(1, MAX_LIST_LIMIT)),
+                prefix: Some(prefix.into()),
+                ..ListOptions::default()
+            };
+            let page = self.list_by_page(options, cursor).await?;
+
+            total += page.keys.len() as u64;
+            f(page.keys).await?;
+
+            match page.next_cursor {
+                Some(next) => cursor = Some(next),
+                None => return Ok(total),
+            }
+        }
+    }
+
+    /// Streams every key in the kv store, fetching the next page only once the current one has
+    /// been consumed. This is [`KvStore::keys_stream`] without a prefix.
+    pub fn list_stream(&self) -> impl Stream<Item = Result<Key, KvError>> {
+        self.keys_stream(None)
+    }
+
+    /// Streams the keys in the kv store, optionally restricted to those starting with `prefix`.
+    ///
+    /// Unlike listing every key up front, the next page is only fetched once all keys of the
+    /// current page have been consumed. The stream ends after the last page, or after yielding the
+    /// error of a failed page request.
+    pub fn keys_stream(&self, prefix: Option<&str>) -> impl Stream<Item = Result<Key, KvError>> {
+        let state = KeysStreamState {
+            store: self.clone(),
+            prefix: prefix.map(Into::into),
+            keys: Vec::new().into_iter(),
+            cursor: None,
+            complete: false,
+        };
+
+        stream::unfold(state, |mut state| async move {
+            loop {
+                if let Some(key) = state.keys.next() {
+                    return Some((Ok(key), state));
+                }
+                if state.complete {
+                    return None;
+                }
+
+                let mut builder = state.store.list();
+                if let Some(prefix) = &state.prefix {
+                    builder = builder.prefix(prefix.clone());
+                }
+                if let Some(cursor) = state.cursor.take() {
+                    builder = builder.cursor(cursor);
+                }
+
+                match builder.execute().await {
+                    Ok(page) => {
+                        state.complete = page.list_complete || page.cursor.is_none();
+                        state.cursor = page.cursor;
+                        state.keys = page.keys.into_iter();
+                    }
+                    Err(e) => {
+                        state.complete = true;
+                        return Some((Err(e), state));
+                    }
+                }
+            }
+        })
+    }
+}
+
+struct KeysStreamState {
+    store: KvStore,
+    prefix: Option<String>,
+    keys: vec::IntoIter<Key>,
+    cursor: Option<String>,
+    complete: bool,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn token(cursor: &str, total_so_far: u64) -> PageToken {
+        PageToken {
+            cursor: KvCursor::from(cursor.to_owned()),
+            total_so_far,
+        }
+    }
+
+    #[test]
+    fn page_tokens_round_trip() {
+        for token in [
+            token("AAAAAFnb0Qv", 1000),
+            token("", 0),
+            token("with:colons:inside", u64::MAX),
+        ] {
+            assert_eq!(PageToken::decode(&token.encode()).unwrap(), token);
+        }
+    }
+
+    #[test]
+    fn page_tokens_are_url_safe() {
+        let encoded = token("a/b+c=d", 42).encode();
+
+        assert!(encoded.bytes().all(|b| b.is_ascii_hexdigit()));
+    }
+
+    #[test]
+    fn rejects_malformed_page_tokens() {
+        let malformed: [&str; 5] = [
+            // Odd length.
+            "313",
+            // Not hex.
+            "zz",
+            // Valid hex, but without a separator.
+            &hex::encode(b"1000"),
+            // The count isn't a number.
+            &hex::encode(b"many:cursor"),
+            // Not UTF-8.
+            "ff3a",
+        ];
+
+        for token in malformed {
+            assert!(
+                matches!(PageToken::decode(token), Err(KvError::InvalidPageToken(t)) if t == token),
+                "{:?} was accepted",
+                token
+            );
+        }
+    }
+}
@@ -0,0 +1,121 @@
+use std::{future::Future, pin::Pin};
+
+use futures_util::future::join_all;
+
+use crate::{KvError, KvStore, KvValue, PutOptionsBuilder, ToRawKvValue};
+
+/// A batch of kv operations created by [`KvStore::pipeline`] that are all sent at once.
+pub struct KvPipeline {
+    store: KvStore,
+    operations: Vec<Operation>,
+}
+
+type OperationFuture = Pin<Box<dyn Future<Output = PipelineOutcome>>>;
+
+enum Operation {
+    Get(String),
+    Put(PutOptionsBuilder),
+    Delete(String),
+}
+
+/// The outcomes of the operations of a [`KvPipeline`], in the order they were queued.
+#[derive(Debug)]
+pub struct PipelineResult {
+    /// The outcome of every operation, in the order they were queued.
+    pub outcomes: Vec<PipelineOutcome>,
+}
+
+/// The outcome of a single operation of a [`KvPipeline`].
+#[derive(Debug)]
+pub enum PipelineOutcome {
+    /// The value read by a [`KvPipeline::get`], or `None` if it was not found.
+    Get(Result<Option<KvValue>, KvError>),
+    /// Whether a [`KvPipeline::put`] succeeded.
+    Put(Result<(), KvError>),
+    /// Whether a [`KvPipeline::delete`] succeeded.
+    Delete(Result<(), KvError>),
+}
+
+impl PipelineOutcome {
+    /// Returns the error the operation failed with, if any.
+    pub fn error(&self) -> Option<&KvError> {
+        match self {
+            PipelineOutcome::Get(result) => result.as_ref().err(),
+            PipelineOutcome::Put(result) | PipelineOutcome::Delete(result) => result.as_ref().err(),
+        }
+    }
+}
+
+impl PipelineResult {
+    /// Returns `true` if every operation of the pipeline succeeded.
+    pub fn is_success(&self) -> bool {
+        self.outcomes
+            .iter()
+            .all(|outcome| outcome.error().is_none())
+    }
+}
+
+impl KvStore {
+    /// Creates a pipeline that queues operations and sends them all at once when executed,
+    /// instead of waiting on each round trip in turn.
+    pub fn pipeline(&self) -> KvPipeline {
+        KvPipeline {
+            store: self.clone(),
+            operations: Vec::new(),
+        }
+    }
+}
+
+impl KvPipeline {
+    /// Queues reading the value stored under `name`.
+    pub fn get(mut self, name: &str) -> Self {
+        self.operations.push(Operation::Get(name.to_owned()));
+        self
+    }
+
+    /// Queues putting the value under `name`, failing straight away if the value can't be
+    /// converted for the kv store.
+    pub fn put<T: ToRawKvValue>(mut self, name: &str, value: T) -> Result<Self, KvError> {
+        let builder = self.store.put(name, value)?;
+        self.operations.push(Operation::Put(builder));
+        Ok(self)
+    }
+
+    /// Queues deleting the value stored under `name`.
+    pub fn delete(mut self, name: &str) -> Self {
+        self.operations.push(Operation::Delete(name.to_owned()));
+        self
+    }
+
+    /// Sends every queued operation at once and waits for all of them to finish.
+    ///
+    /// The operations run concurrently, so there is no ordering between them: a get queued after
+    /// a put of the same key may or may not observe the new value. A failed operation doesn't
+    /// stop the others, its error is reported in its [`PipelineOutcome`].
+    pub async fn execute(self) -> PipelineResult {
+        let store = self.store;
+        let operations = self
+            .operations
+            .into_iter()
+            .map(|operation| -> OperationFuture {
+                let store = store.clone();
+                match operation {
+                    Operation::Get(name) => {
+                        Box::pin(
+                            async move { PipelineOutcome::Get(store.get(&name).value().await) },
+                        )
+                    }
+                    Operation::Put(builder) => {
+                        Box::pin(async move { PipelineOutcome::Put(builder.execute().await) })
+                    }
+                    Operation::Delete(name) => {
+                        Box::pin(async move { PipelineOutcome::Delete(store.delete(&name).await) })
+                    }
+                }
+            });
+
+        PipelineResult {
+            outcomes: join_all(operations).await,
+        }
+    }
+}
@@ -0,0 +1,76 @@
+use crate::{
+    GetOptionsBuilder, KvError, KvStore, ListOptionsBuilder, PutOptionsBuilder, ToRawKvValue,
+};
+
+impl KvStore {
+    /// Creates a view of this store where every key name is prefixed with `prefix`, e.g.
+    /// `"user:123:"`, so callers can't forget to namespace their keys.
+    pub fn with_prefix(&self, prefix: impl Into<String>) -> KvWithPrefix {
+        KvWithPrefix {
+            store: self.clone(),
+            prefix: prefix.into(),
+        }
+    }
+}
+
+/// A view of a kv store that prepends a prefix to every key name, created with
+/// [`KvStore::with_prefix`].
+#[derive(Clone)]
+pub struct KvWithPrefix {
+    store: KvStore,
+    prefix: String,
+}
+
+impl KvWithPrefix {
+    /// Fetches the value stored under the prefixed name.
+    pub fn get(&self, name: &str) -> GetOptionsBuilder {
+        self.store.get(&self.key(name))
+    }
+
+    /// Puts data under the prefixed name.
+    pub fn put<T: ToRawKvValue>(&self, name: &str, value: T) -> Result<PutOptionsBuilder, KvError> {
+        self.store.put(&self.key(name), value)
+    }
+
+    /// Deletes the prefixed key.
+    pub async fn delete(&self, name: &str) -> Result<(), KvError> {
+        self.store.delete(&self.key(name)).await
+    }
+
+    /// Lists the keys under the prefix, optionally restricted further to those continuing with
+    /// `prefix`. The listed key names include the prefix of the view, see
+    /// [`KvWithPrefix::strip_prefix`].
+    ///
+    /// The prefix is set on the returned builder, so don't override it with
+    /// [`ListOptionsBuilder::prefix`].
+    pub fn list(&self, prefix: Option<&str>) -> ListOptionsBuilder {
+        self.store
+            .list()
+            .prefix(self.key(prefix.unwrap_or_default()))
+    }
+
+    /// Checks whether a value is stored under the prefixed name.
+    pub async fn exists(&self, name: &str) -> Result<bool, KvError> {
+        self.store.exists(&self.key(name)).await
+    }
+
+    /// Removes the prefix of the view from a listed key name, returning `None` if it doesn't
+    /// start with the prefix.
+    pub fn strip_prefix<'a>(&self, name: &'a str) -> Option<&'a str> {
+        name.strip_prefix(&self.prefix)
+    }
+
+    /// The prefix prepended to every key name.
+    pub fn prefix(&self) -> &str {
+        &self.prefix
+    }
+
+    /// The underlying store.
+    pub fn store(&self) -> &KvStore {
+        &self.store
+    }
+
+    fn key(&self, name: &str) -> String {
+        format!("{}{}", self.prefix, name)
+    }
+}
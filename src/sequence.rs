@@ -0,0 +1,97 @@
+use std::marker::PhantomData;
+
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::Value;
+
+use crate::{JsonValue, KvError, KvStore};
+
+impl KvStore {
+    /// Appends an item to the JSON array stored under `name`, dropping the oldest items so that
+    /// at most `max_len` items are kept.
+    pub async fn put_sequence<T: Serialize>(
+        &self,
+        name: &str,
+        item: T,
+        max_len: usize,
+    ) -> Result<(), KvError> {
+        let mut items = self.get_sequence::<Value>(name).await?;
+        items.push(serde_json::to_value(item)?);
+        if items.len() > max_len {
+            items.drain(..items.len() - max_len);
+        }
+
+        self.put(name, JsonValue(&items))?.execute().await
+    }
+
+    /// Gets the items of the JSON array stored under `name`, which is empty if the key is missing.
+    pub async fn get_sequence<T: DeserializeOwned>(&self, name: &str) -> Result<Vec<T>, KvError> {
+        Ok(self.get(name).json().await?.unwrap_or_default())
+    }
+
+    /// Creates a handle to the ordered list of items stored under `key`.
+    pub fn sequence<T>(&self, key: &str) -> KvSequence<T> {
+        KvSequence {
+            store: self.clone(),
+            name: key.into(),
+            max_len: usize::MAX,
+            item_type: PhantomData,
+        }
+    }
+}
+
+/// An ordered list of items stored as a JSON array under a single key.
+///
+/// Every operation reads and rewrites the whole array, so this is only suited for short lists
+/// such as recent activity or a small event log.
+pub struct KvSequence<T> {
+    store: KvStore,
+    name: String,
+    max_len: usize,
+    item_type: PhantomData<T>,
+}
+
+impl<T: Serialize + DeserializeOwned> KvSequence<T> {
+    /// The maximum number of items kept in the sequence, older items are dropped when pushing
+    /// past it. By default the sequence is unbounded.
+    pub fn max_len(mut self, max_len: usize) -> Self {
+        self.max_len = max_len;
+        self
+    }
+
+    /// Appends an item to the end of the sequence.
+    pub async fn push(&self, item: T) -> Result<(), KvError> {
+        self.store
+            .put_sequence(&self.name, item, self.max_len)
+            .await
+    }
+
+    /// Gets all of the items in the sequence, oldest first.
+    pub async fn items(&self) -> Result<Vec<T>, KvError> {
+        self.store.get_sequence(&self.name).await
+    }
+
+    /// Gets the number of items in the sequence.
+    pub async fn len(&self) -> Result<usize, KvError> {
+        Ok(self.store.get_sequence::<Value>(&self.name).await?.len())
+    }
+
+    /// Returns `true` if the sequence has no items.
+    pub async fn is_empty(&self) -> Result<bool, KvError> {
+        Ok(self.len().await? == 0)
+    }
+
+    /// Removes the most recently pushed item from the sequence and returns it.
+    pub async fn pop(&self) -> Result<Option<T>, KvError> {
+        let mut items = self.store.get_sequence::<Value>(&self.name).await?;
+        let item = match items.pop() {
+            Some(item) => item,
+            None => return Ok(None),
+        };
+
+        self.store
+            .put(&self.name, JsonValue(&items))?
+            .execute()
+            .await?;
+        Ok(Some(serde_json::from_value(item)?))
+    }
+}
@@ -0,0 +1,144 @@
+use futures_util::future::join;
+use serde::Serialize;
+
+use crate::{GetOptionsBuilder, KvError, KvStore};
+
+/// The outcome of a put or delete on both a primary and a shadow store. Unless both writes
+/// succeeded the two stores may now differ.
+#[derive(Debug)]
+pub struct ShadowPutResult {
+    /// If the write to the primary store succeeded.
+    pub primary_ok: bool,
+    /// Why the write to the primary store failed, if it did.
+    pub primary_error: Option<KvError>,
+    /// If the write to the shadow store succeeded.
+    pub shadow_ok: bool,
+    /// Why the write to the shadow store failed, if it did.
+    pub shadow_error: Option<KvError>,
+}
+
+impl ShadowPutResult {
+    fn new(primary: Result<(), KvError>, shadow: Result<(), KvError>) -> Self {
+        Self {
+            primary_ok: primary.is_ok(),
+            primary_error: primary.err(),
+            shadow_ok: shadow.is_ok(),
+            shadow_error: shadow.err(),
+        }
+    }
+}
+
+impl KvStore {
+    /// Concurrently puts the value as JSON into this store and into `shadow`, e.g. the namespace
+    /// being migrated to.
+    ///
+    /// The outcome of both writes is reported in the result, so a caller can tell when only one
+    /// of the stores was written. Only a value that can't be serialized is returned as an error,
+    /// as nothing is written then.
+    pub async fn shadow_put<T: Serialize>(
+        &self,
+        shadow: &KvStore,
+        name: &str,
+        value: &T,
+    ) -> Result<ShadowPutResult, KvError> {
+        let value = serde_json::to_string(value)?;
+        let put = |store: &KvStore| {
+            let builder = store.put(name, &value);
+            async move { builder?.execute().await }
+        };
+
+        let (primary, shadow) = join(put(self), put(shadow)).await;
+        Ok(ShadowPutResult::new(primary, shadow))
+    }
+
+    /// Concurrently deletes the key from this store and from `shadow`, reporting the outcome of
+    /// both deletes in the result like [`KvStore::shadow_put`].
+    pub async fn shadow_delete(
+        &self,
+        shadow: &KvStore,
+        name: &str,
+    ) -> Result<ShadowPutResult, KvError> {
+        let (primary, shadow) = join(self.delete(name), shadow.delete(name)).await;
+        Ok(ShadowPutResult::new(primary, shadow))
+    }
+
+    /// Wraps this store and `shadow` so that all writes go to both, for the duration of a
+    /// migration to the shadow store.
+    pub fn shadow_store(&self, shadow: &KvStore) -> ShadowStore {
+        ShadowStore {
+            primary: self.clone(),
+            shadow: shadow.clone(),
+        }
+    }
+}
+
+/// A primary store whose writes are mirrored to a shadow store, created with
+/// [`KvStore::shadow_store`]. Reads are only served by the primary store.
+#[derive(Clone)]
+pub struct ShadowStore {
+    primary: KvStore,
+    shadow: KvStore,
+}
+
+impl ShadowStore {
+    /// Fetches the value from the primary store by name.
+    pub fn get(&self, name: &str) -> GetOptionsBuilder {
+        self.primary.get(name)
+    }
+
+    /// Puts the value as JSON into both stores, see [`KvStore::shadow_put`].
+    pub async fn put<T: Serialize>(
+        &self,
+        name: &str,
+        value: &T,
+    ) -> Result<ShadowPutResult, KvError> {
+        self.primary.shadow_put(&self.shadow, name, value).await
+    }
+
+    /// Deletes the key from both stores, see [`KvStore::shadow_delete`].
+    pub async fn delete(&self, name: &str) -> Result<ShadowPutResult, KvError> {
+        self.primary.shadow_delete(&self.shadow, name).await
+    }
+
+    /// The primary store.
+    pub fn primary(&self) -> &KvStore {
+        &self.primary
+    }
+
+    /// The shadow store.
+    pub fn shadow(&self) -> &KvStore {
+        &self.shadow
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn missing_store() -> KvError {
+        KvError::InvalidKvStore("NEW_NAMESPACE".into())
+    }
+
+    #[test]
+    fn reports_a_failed_primary_next_to_a_written_shadow() {
+        let result = ShadowPutResult::new(Err(missing_store()), Ok(()));
+
+        assert!(!result.primary_ok);
+        assert!(matches!(
+            result.primary_error,
+            Some(KvError::InvalidKvStore(_))
+        ));
+        assert!(result.shadow_ok);
+        assert!(result.shadow_error.is_none());
+    }
+
+    #[test]
+    fn reports_a_failed_shadow_next_to_a_written_primary() {
+        let result = ShadowPutResult::new(Ok(()), Err(missing_store()));
+
+        assert!(result.primary_ok);
+        assert!(result.primary_error.is_none());
+        assert!(!result.shadow_ok);
+        assert!(result.shadow_error.is_some());
+    }
+}
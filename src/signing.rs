@@ -0,0 +1,68 @@
+use hmac::{Hmac, Mac};
+use serde_json::Value;
+use sha2::Sha256;
+
+use crate::{hex, KvError, KvStore, PutOptionsBuilder};
+
+/// The metadata field the hex encoded signature of a value is stored in.
+const SIGNATURE_FIELD: &str = "__sig";
+
+type HmacSha256 = Hmac<Sha256>;
+
+impl KvStore {
+    /// Puts the string into the kv store along with its HMAC-SHA256 signature, stored hex encoded
+    /// under `__sig` in the metadata, so [`KvStore::get_verified`] can detect tampering.
+    ///
+    /// Any metadata set on the returned builder must be an object so the signature can be stored
+    /// alongside it.
+    pub fn put_signed(
+        &self,
+        name: &str,
+        value: &str,
+        secret_key: &[u8],
+    ) -> Result<PutOptionsBuilder, KvError> {
+        let signature = mac(value, secret_key).finalize().into_bytes();
+        let signature = hex::encode(&signature);
+
+        Ok(self
+            .put(name, value)?
+            .reserved_metadata(SIGNATURE_FIELD, signature.into()))
+    }
+
+    /// Gets the string stored under `name` with [`KvStore::put_signed`], returning
+    /// [`KvError::SignatureInvalid`] if its signature is missing or does not match the value.
+    pub async fn get_verified(
+        &self,
+        name: &str,
+        secret_key: &[u8],
+    ) -> Result<Option<String>, KvError> {
+        let (value, metadata) = self.get(name).text_with_metadata::<Value>().await?;
+        let value = match value {
+            Some(value) => value,
+            None => return Ok(None),
+        };
+
+        let signature = metadata
+            .as_ref()
+            .and_then(|metadata| metadata.get(SIGNATURE_FIELD))
+            .and_then(Value::as_str)
+            .and_then(hex::decode)
+            .ok_or(KvError::SignatureInvalid)?;
+
+        // `verify_slice` compares in constant time, so the comparison leaks nothing about the
+        // expected signature.
+        mac(&value, secret_key)
+            .verify_slice(&signature)
+            .map_err(|_| KvError::SignatureInvalid)?;
+
+        Ok(Some(value))
+    }
+}
+
+/// Creates a mac for the value keyed with the secret.
+fn mac(value: &str, secret_key: &[u8]) -> HmacSha256 {
+    // HMAC accepts keys of any length, so this can never fail.
+    let mut mac = HmacSha256::new_from_slice(secret_key).expect("hmac accepts keys of any length");
+    mac.update(value.as_bytes());
+    mac
+}
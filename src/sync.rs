@@ -0,0 +1,153 @@
+use std::collections::HashMap;
+
+use futures_util::future::join_all;
+use serde_json::Value;
+
+use crate::{Key, KvError, KvStore};
+
+/// The outcome of a [`KvStore::sync_to`] call.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SyncResult {
+    /// How many keys were written to the target, either because they were missing or changed.
+    pub inserted: u64,
+    /// How many keys were deleted from the target because they no longer exist in the source.
+    pub deleted: u64,
+    /// How many keys were left untouched because they were already identical.
+    pub skipped: u64,
+}
+
+/// The outcome of a [`KvStore::clone_all_to`] call.
+#[derive(Debug, Default)]
+pub struct CloneResult {
+    /// How many keys were copied to the target.
+    pub copied: u64,
+    /// The keys that could not be copied along with the reason.
+    pub failed: Vec<(String, KvError)>,
+}
+
+impl KvStore {
+    /// Makes `target` mirror the keys of this store, optionally restricted to those starting with
+    /// `prefix`.
+    ///
+    /// Keys that are missing from `target` or whose value differs are copied over along with
+    /// their metadata, and keys only present in `target` are deleted. When both keys carry a
+    /// `version` field in their metadata the versions are compared instead of fetching the values.
+    pub async fn sync_to(
+        &self,
+        target: &KvStore,
+        prefix: Option<&str>,
+    ) -> Result<SyncResult, KvError> {
+        let source_keys = self.list_keys(prefix).await?;
+        let mut target_keys: HashMap<String, Key> = target
+            .list_keys(prefix)
+            .await?
+            .into_iter()
+            .map(|key| (key.name.clone(), key))
+            .collect();
+        let mut result = SyncResult::default();
+
+        for key in source_keys {
+            let identical = match target_keys.remove(&key.name) {
+                Some(existing) => self.is_identical_to(target, &key, &existing).await?,
+                None => false,
+            };
+
+            if identical {
+                result.skipped += 1;
+            } else if self.copy_entry(&key.name, target, &key.name).await? {
+                result.inserted += 1;
+            }
+        }
+
+        for name in target_keys.into_keys() {
+            target.delete(&name).await?;
+            result.deleted += 1;
+        }
+
+        Ok(result)
+    }
+
+    /// Copies the keys of `source`, optionally restricted to those starting with `prefix`, that
+    /// are missing from this store along with their metadata. Existing keys are never overwritten.
+    /// Returns the number of newly inserted keys.
+    ///
+    /// Unlike [`KvStore::sync_to`], keys that only exist in this store are left alone.
+    pub async fn merge_from(&self, source: &KvStore, prefix: Option<&str>) -> Result<u64, KvError> {
+        let mut inserted = 0;
+
+        for key in source.list_keys(prefix).await? {
+            if !self.exists(&key.name).await?
+                && source.copy_entry(&key.name, self, &key.name).await?
+            {
+                inserted += 1;
+            }
+        }
+
+        Ok(inserted)
+    }
+
+    /// Copies every key, optionally restricted to those starting with `prefix`, into `target`
+    /// along with its metadata, overwriting existing keys.
+    ///
+    /// Keys are copied concurrently in batches of `batch_size` to stay within the subrequest
+    /// limits, and `on_progress` is called with the number of processed and total keys after
+    /// every batch. Expirations are not carried over, and a failure to copy one key doesn't stop
+    /// the others from being copied.
+    pub async fn clone_all_to(
+        &self,
+        target: &KvStore,
+        prefix: Option<&str>,
+        batch_size: usize,
+        on_progress: Option<&dyn Fn(usize, usize)>,
+    ) -> Result<CloneResult, KvError> {
+        let keys = self.list_keys(prefix).await?;
+        let mut result = CloneResult::default();
+        let mut processed = 0;
+
+        for batch in keys.chunks(batch_size.max(1)) {
+            let copies = batch
+                .iter()
+                .map(|key| self.copy_entry(&key.name, target, &key.name));
+
+            for (key, copy) in batch.iter().zip(join_all(copies).await) {
+                match copy {
+                    Ok(true) => result.copied += 1,
+                    Ok(false) => {}
+                    Err(e) => result.failed.push((key.name.clone(), e)),
+                }
+            }
+
+            processed += batch.len();
+            if let Some(on_progress) = on_progress {
+                on_progress(processed, keys.len());
+            }
+        }
+
+        Ok(result)
+    }
+
+    async fn is_identical_to(
+        &self,
+        target: &KvStore,
+        key: &Key,
+        existing: &Key,
+    ) -> Result<bool, KvError> {
+        if let (Some(version), Some(existing_version)) =
+            (metadata_version(key), metadata_version(existing))
+        {
+            return Ok(version == existing_version);
+        }
+
+        let (value, metadata) = self.get(&key.name).bytes_with_metadata::<Value>().await?;
+        let (existing_value, existing_metadata) = target
+            .get(&existing.name)
+            .bytes_with_metadata::<Value>()
+            .await?;
+
+        Ok(value == existing_value && metadata == existing_metadata)
+    }
+}
+
+fn metadata_version(key: &Key) -> Option<&Value> {
+    key.metadata.as_ref()?.get("version")
+}
@@ -0,0 +1,86 @@
+use std::collections::HashMap;
+
+use crate::{KvError, KvStore, PutOptionsBuilder};
+
+impl KvStore {
+    /// Puts `template` into the kv store with every `{{var_name}}` placeholder replaced by its
+    /// value in `vars`. Placeholders without a value are kept as is.
+    pub fn put_template(
+        &self,
+        name: &str,
+        template: &str,
+        vars: &HashMap<&str, &str>,
+    ) -> Result<PutOptionsBuilder, KvError> {
+        self.put(name, render(template, vars))
+    }
+
+    /// Gets the template stored under `name` with every `{{var_name}}` placeholder replaced by its
+    /// value in `vars`. Placeholders without a value are kept as is.
+    pub async fn get_template(
+        &self,
+        name: &str,
+        vars: &HashMap<&str, &str>,
+    ) -> Result<Option<String>, KvError> {
+        let template = self.get(name).text().await?;
+        Ok(template.map(|template| render(&template, vars)))
+    }
+}
+
+fn render(template: &str, vars: &HashMap<&str, &str>) -> String {
+    let mut rendered = String::with_capacity(template.len());
+    let mut rest = template;
+
+    while let Some(start) = rest.find("{{") {
+        let end = match rest[start..].find("}}") {
+            Some(end) => start + end,
+            None => break,
+        };
+
+        rendered.push_str(&rest[..start]);
+        match vars.get(rest[start + 2..end].trim()) {
+            Some(value) => rendered.push_str(value),
+            None => rendered.push_str(&rest[start..end + 2]),
+        }
+        rest = &rest[end + 2..];
+    }
+
+    rendered.push_str(rest);
+    rendered
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vars() -> HashMap<&'static str, &'static str> {
+        HashMap::from([("name", "Ferris"), ("lang", "Rust")])
+    }
+
+    #[test]
+    fn replaces_repeated_placeholders() {
+        assert_eq!(
+            render("{{name}} writes {{ lang }}, {{name}}!", &vars()),
+            "Ferris writes Rust, Ferris!"
+        );
+    }
+
+    #[test]
+    fn keeps_missing_placeholders() {
+        assert_eq!(
+            render("{{name}} likes {{food}}", &vars()),
+            "Ferris likes {{food}}"
+        );
+    }
+
+    #[test]
+    fn keeps_unterminated_placeholders() {
+        assert_eq!(render("{{name}} and {{lang", &vars()), "Ferris and {{lang");
+        assert_eq!(render("{{", &vars()), "{{");
+    }
+
+    #[test]
+    fn leaves_templates_without_placeholders_unchanged() {
+        assert_eq!(render("plain } text {", &vars()), "plain } text {");
+        assert_eq!(render("", &vars()), "");
+    }
+}
@@ -0,0 +1,333 @@
+//! Utilities for unit testing code that uses a kv store without a JavaScript runtime.
+//!
+//! Only available in tests of this crate or with the `test-utils` feature enabled.
+
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex, MutexGuard},
+};
+
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::Value;
+
+use crate::{
+    validate_key, Key, KvBackend, KvError, KvValue, ListOptions, ListResponse, PutOptions,
+    MAX_LIST_LIMIT, MAX_METADATA_SIZE, MAX_VALUE_SIZE,
+};
+
+/// A value stored in a [`FakeKvStore`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct FakeKvEntry {
+    /// The raw bytes of the value.
+    pub value: Vec<u8>,
+    /// The metadata stored with the value.
+    pub metadata: Option<Value>,
+    /// When (expressed as a [unix timestamp](https://en.wikipedia.org/wiki/Unix_time)) the value
+    /// expires.
+    pub expiration: Option<u64>,
+}
+
+/// An in-memory stand-in for [`KvStore`](crate::KvStore) with the same operations, along with
+/// methods to inspect what happened to it in assertions.
+///
+/// Clones share the same entries, so a clone can be handed to the code under test while the
+/// original is kept for assertions. Expired entries are treated as missing, and invalid keys as
+/// well as values and metadata over the size limits are rejected, like in KV.
+#[derive(Debug, Clone, Default)]
+pub struct FakeKvStore {
+    state: Arc<Mutex<FakeState>>,
+}
+
+#[derive(Debug, Default)]
+struct FakeState {
+    entries: HashMap<String, FakeKvEntry>,
+    put_count: usize,
+    deleted: Vec<String>,
+}
+
+impl FakeKvStore {
+    /// Creates an empty store.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Gets the raw value stored under `name`.
+    pub async fn get(&self, name: &str) -> Result<Option<KvValue>, KvError> {
+        validate_key(name)?;
+        Ok(self.entry(name).map(|entry| KvValue(entry.value)))
+    }
+
+    /// Gets the value stored under `name` as raw bytes.
+    pub async fn get_bytes(&self, name: &str) -> Result<Option<Vec<u8>>, KvError> {
+        Ok(self.get(name).await?.map(KvValue::into_bytes))
+    }
+
+    /// Checks whether a value is stored under `name`.
+    pub async fn exists(&self, name: &str) -> Result<bool, KvError> {
+        Ok(self.get(name).await?.is_some())
+    }
+
+    /// Gets the value stored under `name` as a string.
+    pub async fn get_text(&self, name: &str) -> Result<Option<String>, KvError> {
+        Ok(self.get(name).await?.map(|value| value.as_string()))
+    }
+
+    /// Gets the value stored under `name` deserialized from JSON.
+    pub async fn get_json<T: DeserializeOwned>(&self, name: &str) -> Result<Option<T>, KvError> {
+        self.get(name)
+            .await?
+            .map(|value| value.as_json())
+            .transpose()
+    }
+
+    /// Gets the raw value stored under `name` along with its metadata.
+    pub async fn get_with_metadata<M: DeserializeOwned>(
+        &self,
+        name: &str,
+    ) -> Result<(Option<KvValue>, Option<M>), KvError> {
+        validate_key(name)?;
+        let entry = match self.entry(name) {
+            Some(entry) => entry,
+            None => return Ok((None, None)),
+        };
+
+        let metadata = entry.metadata.map(M::deserialize).transpose()?;
+        Ok((Some(KvValue(entry.value)), metadata))
+    }
+
+    /// Puts the string under `name`.
+    pub async fn put(&self, name: &str, value: &str) -> Result<(), KvError> {
+        self.put_with_options(name, value.as_bytes(), PutOptions::default())
+            .await
+    }
+
+    /// Puts the value as JSON under `name`.
+    pub async fn put_json<T: Serialize>(&self, name: &str, value: &T) -> Result<(), KvError> {
+        let value = serde_json::to_vec(value)?;
+        self.put_with_options(name, &value, PutOptions::default())
+            .await
+    }
+
+    /// Puts the bytes under `name`.
+    pub async fn put_bytes(&self, name: &str, value: &[u8]) -> Result<(), KvError> {
+        self.put_with_options(name, value, PutOptions::default())
+            .await
+    }
+
+    /// Puts the bytes under `name` with the given expiration and metadata.
+    pub async fn put_with_options(
+        &self,
+        name: &str,
+        value: &[u8],
+        options: PutOptions,
+    ) -> Result<(), KvError> {
+        validate_key(name)?;
+        if value.len() > MAX_VALUE_SIZE {
+            return Err(KvError::ValueTooLarge {
+                actual_bytes: value.len(),
+            });
+        }
+        if let Some(metadata) = &options.metadata {
+            let actual_bytes = serde_json::to_string(metadata)?.len();
+            if actual_bytes > MAX_METADATA_SIZE {
+                return Err(KvError::MetadataTooLarge { actual_bytes });
+            }
+        }
+
+        let expiration = options
+            .expiration
+            .or_else(|| options.expiration_ttl.map(|ttl| now() + ttl));
+
+        let mut state = self.state();
+        state.put_count += 1;
+        state.entries.insert(
+            name.to_owned(),
+            FakeKvEntry {
+                value: value.to_vec(),
+                metadata: options.metadata,
+                expiration,
+            },
+        );
+        Ok(())
+    }
+
+    /// Lists the keys in lexicographic order, following the same options as
+    /// [`KvStore::list`](crate::KvStore::list). The cursor is the offset into the listing.
+    pub async fn list(&self, options: ListOptions) -> Result<ListResponse, KvError> {
+        let now = now();
+        let state = self.state();
+
+        let mut keys: Vec<Key> = state
+            .entries
+            .iter()
+            .filter(|(name, _)| {
+                options
+                    .prefix
+                    .as_deref()
+                    .is_none_or(|prefix| name.starts_with(prefix))
+            })
+            .filter(|(_, entry)| is_live(entry, now))
+            .map(|(name, entry)| Key {
+                name: name.clone(),
+                expiration: entry.expiration,
+                metadata: entry.metadata.clone(),
+            })
+            .collect();
+        keys.sort_by(|a, b| a.name.cmp(&b.name));
+
+        let offset: usize = match &options.cursor {
+            Some(cursor) => cursor
+                .parse()
+                .map_err(|_| KvError::InvalidPageToken(cursor.clone()))?,
+            None => 0,
+        };
+        let limit = options.limit.unwrap_or(MAX_LIST_LIMIT) as usize;
+        let end = keys.len().min(offset.saturating_add(limit));
+        let list_complete = end == keys.len();
+
+        Ok(ListResponse {
+            keys: keys.drain(offset.min(end)..end).collect(),
+            list_complete,
+            cursor: (!list_complete).then(|| end.to_string()),
+        })
+    }
+
+    /// Lists every key by following the cursors of [`FakeKvStore::list`] until the listing is
+    /// complete, like [`KvStore::list_all`](crate::KvStore::list_all).
+    pub async fn list_all(&self) -> Result<Vec<Key>, KvError> {
+        self.list_all_matching(None).await
+    }
+
+    /// Like [`FakeKvStore::list_all`], but only lists the keys starting with `prefix`.
+    pub async fn list_all_with_prefix(&self, prefix: &str) -> Result<Vec<Key>, KvError> {
+        self.list_all_matching(Some(prefix)).await
+    }
+
+    /// Renews the expiration of a key to `ttl` seconds from now by putting its value back with
+    /// the same metadata, which counts as a put. Returns `false` if the key doesn't exist.
+    pub async fn touch(&self, name: &str, ttl: u64) -> Result<bool, KvError> {
+        let entry = match self.entry(name) {
+            Some(entry) => entry,
+            None => return Ok(false),
+        };
+
+        let options = PutOptions {
+            expiration_ttl: Some(ttl),
+            metadata: entry.metadata,
+            ..PutOptions::default()
+        };
+        self.put_with_options(name, &entry.value, options).await?;
+        Ok(true)
+    }
+
+    /// Deletes the value stored under `name`.
+    pub async fn delete(&self, name: &str) -> Result<(), KvError> {
+        validate_key(name)?;
+        let mut state = self.state();
+        state.entries.remove(name);
+        state.deleted.push(name.to_owned());
+        Ok(())
+    }
+
+    /// Panics unless exactly `expected` puts were made.
+    #[track_caller]
+    pub fn assert_put_count(&self, expected: usize) {
+        let actual = self.state().put_count;
+        assert_eq!(actual, expected, "expected {expected} puts, got {actual}");
+    }
+
+    /// Panics unless `name` was deleted at some point.
+    #[track_caller]
+    pub fn assert_deleted(&self, name: &str) {
+        let state = self.state();
+        assert!(
+            state.deleted.iter().any(|deleted| deleted == name),
+            "expected {name:?} to be deleted, deleted keys: {:?}",
+            state.deleted
+        );
+    }
+
+    /// A copy of every entry currently in the store, including expired ones.
+    pub fn snapshot(&self) -> HashMap<String, FakeKvEntry> {
+        self.state().entries.clone()
+    }
+
+    async fn list_all_matching(&self, prefix: Option<&str>) -> Result<Vec<Key>, KvError> {
+        let mut keys = Vec::new();
+        let mut cursor = None;
+
+        loop {
+            let page = self
+                .list(ListOptions {
+                    prefix: prefix.map(str::to_owned),
+                    limit: None,
+                    cursor,
+                })
+                .await?;
+            keys.extend(page.keys);
+
+            if page.list_complete {
+                return Ok(keys);
+            }
+            cursor = page.cursor;
+        }
+    }
+
+    fn entry(&self, name: &str) -> Option<FakeKvEntry> {
+        let now = now();
+        self.state()
+            .entries
+            .get(name)
+            .filter(|entry| is_live(entry, now))
+            .cloned()
+    }
+
+    fn state(&self) -> MutexGuard<'_, FakeState> {
+        // A panicking assertion in another test thread must not take this store down with it.
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl KvBackend for FakeKvStore {
+    async fn get(&self, name: &str) -> Result<Option<KvValue>, KvError> {
+        FakeKvStore::get(self, name).await
+    }
+
+    async fn get_with_metadata<M: DeserializeOwned>(
+        &self,
+        name: &str,
+    ) -> Result<(Option<KvValue>, Option<M>), KvError> {
+        FakeKvStore::get_with_metadata(self, name).await
+    }
+
+    async fn put(&self, name: &str, value: &[u8], options: PutOptions) -> Result<(), KvError> {
+        self.put_with_options(name, value, options).await
+    }
+
+    async fn list(&self, options: ListOptions) -> Result<ListResponse, KvError> {
+        FakeKvStore::list(self, options).await
+    }
+
+    async fn delete(&self, name: &str) -> Result<(), KvError> {
+        FakeKvStore::delete(self, name).await
+    }
+}
+
+fn is_live(entry: &FakeKvEntry, now: u64) -> bool {
+    entry.expiration.is_none_or(|expiration| expiration > now)
+}
+
+/// The current unix timestamp in seconds.
+fn now() -> u64 {
+    #[cfg(target_arch = "wasm32")]
+    {
+        (js_sys::Date::now() / 1000.0) as u64
+    }
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|duration| duration.as_secs())
+            .unwrap_or_default()
+    }
+}
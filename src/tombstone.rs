@@ -0,0 +1,52 @@
+use js_sys::Date;
+use serde_json::{json, Value};
+
+use crate::{KvError, KvStore, KvValue};
+
+/// The field holding when a key was deleted with [`KvStore::tombstone_delete`], in milliseconds
+/// since the unix epoch.
+const TOMBSTONED_AT_FIELD: &str = "__tombstoned_at";
+
+impl KvStore {
+    /// Soft-deletes `name` by overwriting it with a `{"__tombstoned_at": unix_ms}` tombstone that
+    /// expires after `tombstone_ttl_secs` seconds, after which KV removes the key for good.
+    ///
+    /// The tombstone is also recorded in the metadata, so [`KvStore::is_tombstoned`] can't mistake
+    /// a regular value for one.
+    pub async fn tombstone_delete(
+        &self,
+        name: &str,
+        tombstone_ttl_secs: u64,
+    ) -> Result<(), KvError> {
+        let tombstoned_at = Date::now() as u64;
+        self.put(name, json!({ TOMBSTONED_AT_FIELD: tombstoned_at }))?
+            .expiration_ttl(tombstone_ttl_secs)
+            .reserved_metadata(TOMBSTONED_AT_FIELD, tombstoned_at.into())
+            .execute()
+            .await
+    }
+
+    /// Returns `true` if `name` currently holds a tombstone written by
+    /// [`KvStore::tombstone_delete`].
+    pub async fn is_tombstoned(&self, name: &str) -> Result<bool, KvError> {
+        let (_, metadata) = self.get(name).text_with_metadata::<Value>().await?;
+        Ok(is_tombstone(metadata.as_ref()))
+    }
+
+    /// Gets the value stored under `name`, treating a tombstone written by
+    /// [`KvStore::tombstone_delete`] as a missing key.
+    pub async fn get_non_tombstoned(&self, name: &str) -> Result<Option<KvValue>, KvError> {
+        let (value, metadata) = self.get(name).bytes_with_metadata::<Value>().await?;
+        if is_tombstone(metadata.as_ref()) {
+            return Ok(None);
+        }
+
+        Ok(value.map(KvValue))
+    }
+}
+
+fn is_tombstone(metadata: Option<&Value>) -> bool {
+    metadata
+        .and_then(|metadata| metadata.get(TOMBSTONED_AT_FIELD))
+        .is_some()
+}
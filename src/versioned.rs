@@ -0,0 +1,54 @@
+use serde::{de::DeserializeOwned, Serialize};
+
+use crate::{JsonValue, KvError, KvStore};
+
+impl KvStore {
+    /// Puts the value as JSON under a new `__v_{name}_{version}` key and points `name` at it,
+    /// keeping every previous version readable with [`KvStore::get_version`]. Returns the new
+    /// version, counting up from `1` in a counter stored under `__vc_{name}`.
+    ///
+    /// Incrementing the counter is a read-modify-write, so concurrent writers can end up with the
+    /// same version and overwrite each other.
+    pub async fn put_versioned<T: Serialize>(&self, name: &str, value: &T) -> Result<u64, KvError> {
+        let counter = format!("__vc_{}", name);
+        let version = self.get_number(&counter).await?.unwrap_or(0) + 1;
+        self.put_number(&counter, version).await?;
+
+        self.put(&version_key(name, version as u64), JsonValue(value))?
+            .execute()
+            .await?;
+        self.put_number(name, version).await?;
+
+        Ok(version as u64)
+    }
+
+    /// Gets the version `name` currently points at along with its version number.
+    pub async fn get_latest<T: DeserializeOwned>(
+        &self,
+        name: &str,
+    ) -> Result<Option<(T, u64)>, KvError> {
+        let version = match self.get_number(name).await? {
+            Some(version) => version as u64,
+            None => return Ok(None),
+        };
+
+        Ok(self
+            .get_version(name, version)
+            .await?
+            .map(|value| (value, version)))
+    }
+
+    /// Gets a specific version stored with [`KvStore::put_versioned`].
+    pub async fn get_version<T: DeserializeOwned>(
+        &self,
+        name: &str,
+        version: u64,
+    ) -> Result<Option<T>, KvError> {
+        self.get(&version_key(name, version)).json().await
+    }
+}
+
+/// The key a version of `name` is stored under.
+fn version_key(name: &str, version: u64) -> String {
+    format!("__v_{}_{}", name, version)
+}
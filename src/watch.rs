@@ -0,0 +1,241 @@
+use std::{
+    collections::HashSet,
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+    time::Duration,
+};
+
+use futures_util::{future::poll_fn, ready};
+use gloo_timers::future::sleep;
+
+use crate::{Key, KvError, KvStore, KvValue};
+
+impl KvStore {
+    /// Watches the value stored under `name` for changes, polling the kv store every
+    /// `interval_ms` milliseconds.
+    ///
+    /// Keep in mind that kv changes can take a minute to become visible to other workers, so a
+    /// short interval mostly burns through read quota.
+    pub fn watch(&self, name: &str, interval_ms: u32) -> KvWatcher {
+        KvWatcher {
+            state: Some(WatchState {
+                store: self.clone(),
+                name: name.into(),
+                interval_ms,
+                last: None,
+            }),
+            pending: None,
+        }
+    }
+
+    /// Watches the keys starting with `prefix` for keys being added or removed, listing them every
+    /// `poll_interval_ms` milliseconds.
+    ///
+    /// Every poll lists all of the keys under the prefix, so this is best kept to small prefixes.
+    /// Like [`KvStore::watch`] keep in mind that kv changes can take a minute to become visible.
+    pub fn watch_list(&self, prefix: &str, poll_interval_ms: u32) -> KvListWatcher {
+        KvListWatcher {
+            state: Some(ListWatchState {
+                store: self.clone(),
+                prefix: prefix.into(),
+                interval_ms: poll_interval_ms,
+                snapshot: None,
+            }),
+            pending: None,
+        }
+    }
+}
+
+/// A change of the value of a watched key.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KvChange {
+    /// The value before the change, `None` if the key did not exist.
+    pub old: Option<KvValue>,
+    /// The value after the change, `None` if the key was deleted.
+    pub new: Option<KvValue>,
+}
+
+/// Polls a key for changes, created with [`KvStore::watch`].
+///
+/// With the `futures` feature enabled this also implements `futures::Stream`.
+#[must_use = "KvWatcher does nothing until you poll it for changes"]
+pub struct KvWatcher {
+    state: Option<WatchState>,
+    pending: Option<PendingChange>,
+}
+
+type PendingChange = Pin<Box<dyn Future<Output = (WatchState, Result<KvChange, KvError>)>>>;
+
+impl KvWatcher {
+    /// Waits until the value of the watched key differs from the last seen value. The first call
+    /// compares against the value at the time of the call.
+    pub async fn next_change(&mut self) -> Result<KvChange, KvError> {
+        poll_fn(|cx| self.poll_change(cx)).await
+    }
+
+    fn poll_change(&mut self, cx: &mut Context<'_>) -> Poll<Result<KvChange, KvError>> {
+        if self.pending.is_none() {
+            let state = self
+                .state
+                .take()
+                .expect("watch state is restored after every change");
+            self.pending = Some(Box::pin(state.next_change()));
+        }
+
+        let pending = self.pending.as_mut().expect("pending change was just set");
+        let (state, result) = ready!(pending.as_mut().poll(cx));
+        self.pending = None;
+        self.state = Some(state);
+        Poll::Ready(result)
+    }
+}
+
+#[cfg(feature = "futures")]
+impl futures_core::Stream for KvWatcher {
+    type Item = Result<KvChange, KvError>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.get_mut().poll_change(cx).map(Some)
+    }
+}
+
+struct WatchState {
+    store: KvStore,
+    name: String,
+    interval_ms: u32,
+    last: Option<Option<KvValue>>,
+}
+
+impl WatchState {
+    async fn next_change(mut self) -> (Self, Result<KvChange, KvError>) {
+        let result = self.wait_for_change().await;
+        (self, result)
+    }
+
+    async fn wait_for_change(&mut self) -> Result<KvChange, KvError> {
+        let old = match &self.last {
+            Some(last) => last.clone(),
+            None => {
+                let value = self.store.get(&self.name).value().await?;
+                self.last = Some(value.clone());
+                value
+            }
+        };
+
+        loop {
+            sleep(Duration::from_millis(self.interval_ms.into())).await;
+
+            let new = self.store.get(&self.name).value().await?;
+            if new != old {
+                self.last = Some(new.clone());
+                return Ok(KvChange { old, new });
+            }
+        }
+    }
+}
+
+/// The keys added and removed under a watched prefix.
+#[derive(Debug, Clone)]
+pub struct KvListChange {
+    /// The keys that were added since the last change.
+    pub added: Vec<Key>,
+    /// The names of the keys that were removed since the last change.
+    pub removed: Vec<String>,
+}
+
+/// Polls a prefix for keys being added or removed, created with [`KvStore::watch_list`].
+///
+/// With the `futures` feature enabled this also implements `futures::Stream`.
+#[must_use = "KvListWatcher does nothing until you poll it for changes"]
+pub struct KvListWatcher {
+    state: Option<ListWatchState>,
+    pending: Option<PendingListChange>,
+}
+
+type PendingListChange =
+    Pin<Box<dyn Future<Output = (ListWatchState, Result<KvListChange, KvError>)>>>;
+
+impl KvListWatcher {
+    /// Waits until keys were added or removed since the last seen listing. The first call compares
+    /// against the keys at the time of the call.
+    pub async fn next_change(&mut self) -> Result<KvListChange, KvError> {
+        poll_fn(|cx| self.poll_change(cx)).await
+    }
+
+    fn poll_change(&mut self, cx: &mut Context<'_>) -> Poll<Result<KvListChange, KvError>> {
+        if self.pending.is_none() {
+            let state = self
+                .state
+                .take()
+                .expect("watch state is restored after every change");
+            self.pending = Some(Box::pin(state.next_change()));
+        }
+
+        let pending = self.pending.as_mut().expect("pending change was just set");
+        let (state, result) = ready!(pending.as_mut().poll(cx));
+        self.pending = None;
+        self.state = Some(state);
+        Poll::Ready(result)
+    }
+}
+
+#[cfg(feature = "futures")]
+impl futures_core::Stream for KvListWatcher {
+    type Item = Result<KvListChange, KvError>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.get_mut().poll_change(cx).map(Some)
+    }
+}
+
+struct ListWatchState {
+    store: KvStore,
+    prefix: String,
+    interval_ms: u32,
+    snapshot: Option<HashSet<String>>,
+}
+
+impl ListWatchState {
+    async fn next_change(mut self) -> (Self, Result<KvListChange, KvError>) {
+        let result = self.wait_for_change().await;
+        (self, result)
+    }
+
+    async fn wait_for_change(&mut self) -> Result<KvListChange, KvError> {
+        let old = match self.snapshot.take() {
+            Some(snapshot) => snapshot,
+            None => self.list_names().await?,
+        };
+        // Keep the snapshot in place in case listing fails below.
+        self.snapshot = Some(old.clone());
+
+        loop {
+            sleep(Duration::from_millis(self.interval_ms.into())).await;
+
+            let keys = self.store.list_keys(Some(&self.prefix)).await?;
+            let new: HashSet<String> = keys.iter().map(|key| key.name.clone()).collect();
+            if new == old {
+                continue;
+            }
+
+            let added = keys
+                .into_iter()
+                .filter(|key| !old.contains(&key.name))
+                .collect();
+            let removed = old.difference(&new).cloned().collect();
+            self.snapshot = Some(new);
+            return Ok(KvListChange { added, removed });
+        }
+    }
+
+    async fn list_names(&self) -> Result<HashSet<String>, KvError> {
+        Ok(self
+            .store
+            .list_keys(Some(&self.prefix))
+            .await?
+            .into_iter()
+            .map(|key| key.name)
+            .collect())
+    }
+}
@@ -1,97 +1,155 @@
 use std::{
     io::{self, ErrorKind},
-    net::{SocketAddr, TcpStream},
-    path::Path,
+    net::{SocketAddr, TcpListener, TcpStream},
+    path::PathBuf,
     process::{Child, Command},
-    str::FromStr,
     time::{Duration, Instant},
 };
 
 use fs_extra::dir::CopyOptions;
 use serde::Deserialize;
+use worker_kv_macros::miniflare_test;
 
-#[tokio::test]
-#[allow(clippy::needless_collect)]
-async fn integration_test() {
-    let node_procs_to_ignore = psutil::process::processes()
-        .unwrap()
-        .into_iter()
-        .filter_map(|r| r.ok())
-        .filter(|process| process.name().unwrap() == "node")
-        .map(|p| p.pid())
-        .collect::<Vec<_>>();
-
-    start_miniflare().expect("unable to spawn miniflare, did you install node modules?");
-    wait_for_worker_to_spawn();
+const WORKER_DIR: &str = "tests/worker_kv_test";
 
+#[miniflare_test]
+async fn integration_test(fixture: &TestFixture) {
     let endpoints = [
         "get",
         "get-not-found",
         "list-keys",
         "put-simple",
         "put-metadata",
+        "put-metadata-struct",
         "put-expiration",
+        "sequence",
+        "diagnose",
+        "json-patch",
     ];
 
     for endpoint in endpoints {
-        let text_res = reqwest::get(&format!("http://localhost:8787/{}", endpoint))
-            .await
-            .expect("unable to send request")
-            .text()
-            .await;
-
-        assert!(text_res.is_ok(), "{} failed", endpoint);
-        assert_eq!(text_res.unwrap(), "passed".to_string());
+        fixture.assert_success(endpoint).await;
     }
+}
+
+#[miniflare_test(seed = [("seeded", "seeded value")])]
+async fn seeded_values(fixture: &TestFixture) {
+    fixture.assert_success("get-seeded").await;
+}
+
+/// A miniflare instance running the test worker on its own port and with its own kv persistence
+/// directory, which is killed and cleaned up once dropped.
+struct TestFixture {
+    process: Child,
+    port: u16,
+    persist_dir: PathBuf,
+}
 
-    let processes = psutil::process::processes().unwrap();
+impl TestFixture {
+    /// Starts miniflare with the contents of `.mf-init` and the given key value pairs already in
+    /// the `test` kv store.
+    fn start_with(seed: &[(&str, &str)]) -> io::Result<Self> {
+        let port = free_port()?;
+        let persist_dir = PathBuf::from(WORKER_DIR).join(".mf").join(port.to_string());
 
-    for process in processes.into_iter().filter_map(|r| r.ok()) {
-        if !node_procs_to_ignore.contains(&process.pid()) && process.name().unwrap() == "node" {
-            let _ = process.send_signal(psutil::process::Signal::SIGQUIT);
+        if persist_dir.exists() {
+            std::fs::remove_dir_all(&persist_dir)?;
         }
+        std::fs::create_dir_all(&persist_dir)?;
+
+        fs_extra::dir::copy(
+            PathBuf::from(WORKER_DIR).join(".mf-init"),
+            &persist_dir,
+            &CopyOptions {
+                content_only: true,
+                ..CopyOptions::new()
+            },
+        )
+        .map_err(io::Error::other)?;
+
+        let store_dir = persist_dir.join("kv").join("test");
+        std::fs::create_dir_all(&store_dir)?;
+        for (key, value) in seed {
+            std::fs::write(store_dir.join(key), value)?;
+        }
+
+        let kv_persist = format!(".mf/{}/kv", port);
+        let process = Command::new("../node_modules/.bin/miniflare")
+            .args([
+                "-c",
+                "wrangler.toml",
+                "-k",
+                "test",
+                "--kv-persist",
+                &kv_persist,
+            ])
+            .args(["-p", &port.to_string()])
+            .current_dir(WORKER_DIR)
+            .spawn()?;
+
+        let fixture = Self {
+            process,
+            port,
+            persist_dir,
+        };
+        fixture.wait_for_worker_to_spawn();
+        Ok(fixture)
+    }
+
+    /// Runs the test behind the given endpoint of the test worker.
+    async fn run(&self, endpoint: &str) -> TestResult {
+        let url = format!("http://localhost:{}/{}", self.port, endpoint);
+        let response = match reqwest::get(&url).await {
+            Ok(response) => response,
+            Err(e) => return TestResult::Failure(format!("unable to send request: {}", e)),
+        };
+
+        response
+            .json::<TestResult>()
+            .await
+            .unwrap_or_else(|e| TestResult::Failure(format!("invalid test response: {}", e)))
     }
-}
 
-/// Waits for wrangler to spawn it's http server.
-fn wait_for_worker_to_spawn() {
-    let now = Instant::now();
-    let addr = SocketAddr::from_str("0.0.0.0:8787").unwrap();
-
-    while Instant::now() - now <= Duration::from_secs(5 * 60) {
-        match TcpStream::connect_timeout(&addr, Duration::from_secs(5)) {
-            Ok(_) => return,
-            Err(e)
-                if e.kind() == ErrorKind::ConnectionRefused
-                    || e.kind() == ErrorKind::ConnectionReset => {}
-            Err(e) => Err(e).expect("unexpected error connecting to worker"),
+    /// Runs the test behind the given endpoint, panicking if it failed.
+    async fn assert_success(&self, endpoint: &str) {
+        match self.run(endpoint).await {
+            TestResult::Success(value) => println!("{} passed: {}", endpoint, value),
+            TestResult::Failure(reason) => panic!("{} failed: {}", endpoint, reason),
         }
     }
 
-    panic!("timed out connecting to worker")
-}
+    /// Waits for miniflare to spawn it's http server.
+    fn wait_for_worker_to_spawn(&self) {
+        let now = Instant::now();
+        let addr = SocketAddr::from(([127, 0, 0, 1], self.port));
+
+        while Instant::now() - now <= Duration::from_secs(5 * 60) {
+            match TcpStream::connect_timeout(&addr, Duration::from_secs(5)) {
+                Ok(_) => return,
+                Err(e)
+                    if e.kind() == ErrorKind::ConnectionRefused
+                        || e.kind() == ErrorKind::ConnectionReset => {}
+                Err(e) => panic!("unexpected error connecting to worker: {}", e),
+            }
+        }
 
-fn start_miniflare() -> io::Result<Child> {
-    let mf_path = Path::new("tests/worker_kv_test/.mf");
+        panic!("timed out connecting to worker")
+    }
+}
 
-    if mf_path.exists() {
-        std::fs::remove_dir_all(mf_path)?;
+impl Drop for TestFixture {
+    fn drop(&mut self) {
+        let _ = self.process.kill();
+        let _ = self.process.wait();
+        let _ = std::fs::remove_dir_all(&self.persist_dir);
     }
+}
 
-    fs_extra::dir::copy(
-        "tests/worker_kv_test/.mf-init",
-        mf_path,
-        &CopyOptions {
-            content_only: true,
-            ..CopyOptions::new()
-        },
-    )
-    .unwrap();
-
-    Command::new("../node_modules/.bin/miniflare")
-        .args(&["-c", "wrangler.toml", "-k", "test", "--kv-persist"])
-        .current_dir("tests/worker_kv_test")
-        .spawn()
+/// Asks the OS for a port that is currently not in use.
+fn free_port() -> io::Result<u16> {
+    TcpListener::bind("127.0.0.1:0")?
+        .local_addr()
+        .map(|addr| addr.port())
 }
 
 #[derive(Debug, Deserialize)]
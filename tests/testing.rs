@@ -0,0 +1,201 @@
+//! Tests for the in-memory store of the `testing` module, which run natively without miniflare.
+#![cfg(feature = "test-utils")]
+
+use serde_json::json;
+use worker_kv::{testing::FakeKvStore, validate_key, KvBackend, KvError, ListOptions, PutOptions};
+
+/// Counts the keys through the backend trait, like code under test would.
+async fn count_keys<B: KvBackend>(backend: &B, prefix: &str) -> usize {
+    let options = ListOptions {
+        prefix: Some(prefix.into()),
+        ..ListOptions::default()
+    };
+    backend.list(options).await.unwrap().keys.len()
+}
+
+#[tokio::test]
+async fn fake_store_round_trip() {
+    let store = FakeKvStore::new();
+
+    store.put("a", "one").await.unwrap();
+    store.put_json("b", &json!({ "two": 2 })).await.unwrap();
+    store
+        .put_with_options(
+            "c",
+            b"three",
+            PutOptions {
+                metadata: Some(json!({ "tag": "x" })),
+                ..PutOptions::default()
+            },
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(store.get_text("a").await.unwrap().as_deref(), Some("one"));
+    assert_eq!(
+        store.get_json::<serde_json::Value>("b").await.unwrap(),
+        Some(json!({ "two": 2 }))
+    );
+    let (value, metadata) = store
+        .get_with_metadata::<serde_json::Value>("c")
+        .await
+        .unwrap();
+    assert_eq!(value.unwrap().as_bytes(), b"three");
+    assert_eq!(metadata, Some(json!({ "tag": "x" })));
+
+    store.delete("a").await.unwrap();
+    assert_eq!(store.get("a").await.unwrap(), None);
+    store.assert_put_count(3);
+    store.assert_deleted("a");
+    assert_eq!(store.snapshot().len(), 2);
+}
+
+#[tokio::test]
+async fn fake_store_lists_in_pages() {
+    let store = FakeKvStore::new();
+    for i in 0..5 {
+        store.put(&format!("key/{}", i), "value").await.unwrap();
+    }
+    store.put("other", "value").await.unwrap();
+
+    let first = store
+        .list(ListOptions {
+            prefix: Some("key/".into()),
+            limit: Some(3),
+            cursor: None,
+        })
+        .await
+        .unwrap();
+    assert_eq!(first.keys.len(), 3);
+    assert!(!first.list_complete);
+
+    let second = store
+        .list(ListOptions {
+            prefix: Some("key/".into()),
+            limit: Some(3),
+            cursor: first.cursor,
+        })
+        .await
+        .unwrap();
+    let names: Vec<_> = second.keys.iter().map(|key| key.name.as_str()).collect();
+    assert_eq!(names, ["key/3", "key/4"]);
+    assert!(second.list_complete);
+
+    assert_eq!(count_keys(&store, "key/").await, 5);
+}
+
+#[tokio::test]
+async fn fake_store_hides_expired_entries() {
+    let store = FakeKvStore::new();
+    store
+        .put_with_options(
+            "expired",
+            b"value",
+            PutOptions {
+                expiration: Some(1),
+                ..PutOptions::default()
+            },
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(store.get("expired").await.unwrap(), None);
+    assert_eq!(count_keys(&store, "").await, 0);
+    assert!(store.snapshot().contains_key("expired"));
+}
+
+#[tokio::test]
+async fn fake_store_rejects_invalid_keys() {
+    let store = FakeKvStore::new();
+    let long_key = "a".repeat(513);
+
+    for key in ["", ".", "..", long_key.as_str()] {
+        assert!(matches!(
+            store.put(key, "value").await,
+            Err(KvError::InvalidKey(_))
+        ));
+        assert!(matches!(store.get(key).await, Err(KvError::InvalidKey(_))));
+    }
+    store.assert_put_count(0);
+
+    assert!(validate_key(&"a".repeat(512)).is_ok());
+    let error = validate_key(&long_key).unwrap_err();
+    assert!(error.to_string().contains("513 bytes"), "{}", error);
+}
+
+#[tokio::test]
+async fn fake_store_rejects_oversized_puts() {
+    let store = FakeKvStore::new();
+
+    let value = vec![0; 25 * 1024 * 1024 + 1];
+    assert!(matches!(
+        store.put_bytes("large", &value).await,
+        Err(KvError::ValueTooLarge {
+            actual_bytes: 26_214_401
+        })
+    ));
+
+    let options = PutOptions {
+        metadata: Some(json!({ "padding": "a".repeat(1024) })),
+        ..PutOptions::default()
+    };
+    assert!(matches!(
+        store.put_with_options("large", b"value", options).await,
+        Err(KvError::MetadataTooLarge { .. })
+    ));
+    store.assert_put_count(0);
+}
+
+#[tokio::test]
+async fn fake_store_lists_nothing_past_huge_cursors() {
+    let store = FakeKvStore::new();
+    store.put("key", "value").await.unwrap();
+
+    let response = store
+        .list(ListOptions {
+            cursor: Some(usize::MAX.to_string()),
+            ..ListOptions::default()
+        })
+        .await
+        .unwrap();
+    assert!(response.keys.is_empty());
+    assert!(response.list_complete);
+}
+
+#[tokio::test]
+async fn fake_store_lists_everything_across_pages() {
+    let store = FakeKvStore::new();
+    for i in 0..1500 {
+        store.put(&format!("key/{:04}", i), "value").await.unwrap();
+    }
+    store.put("other", "value").await.unwrap();
+
+    assert_eq!(store.list_all().await.unwrap().len(), 1501);
+    let keys = store.list_all_with_prefix("key/").await.unwrap();
+    assert_eq!(keys.len(), 1500);
+    assert_eq!(keys[1499].name, "key/1499");
+}
+
+#[tokio::test]
+async fn fake_store_touch_keeps_the_value_and_metadata() {
+    let store = FakeKvStore::new();
+    let options = PutOptions {
+        metadata: Some(json!({ "tag": "x" })),
+        ..PutOptions::default()
+    };
+    store
+        .put_with_options("key", b"value", options)
+        .await
+        .unwrap();
+
+    assert!(store.touch("key", 60).await.unwrap());
+    assert!(!store.touch("missing", 60).await.unwrap());
+
+    let entry = store.snapshot().remove("key").unwrap();
+    assert_eq!(entry.value, b"value");
+    assert_eq!(entry.metadata, Some(json!({ "tag": "x" })));
+    assert!(entry.expiration.is_some());
+    assert!(store.exists("key").await.unwrap());
+    assert_eq!(store.get_bytes("key").await.unwrap().unwrap(), b"value");
+    store.assert_put_count(2);
+}
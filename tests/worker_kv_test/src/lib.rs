@@ -1,5 +1,6 @@
 use std::future::Future;
 
+use serde::{Deserialize, Serialize};
 use worker::*;
 use worker_kv::{KvError, KvStore};
 
@@ -30,10 +31,17 @@ pub async fn main(req: Request, env: Env, _ctx: Context) -> Result<Response> {
     Router::with_data(store)
         .get_async("/get", |req, ctx| wrap(req, ctx, get))
         .get_async("/get-not-found", |req, ctx| wrap(req, ctx, get_not_found))
+        .get_async("/get-seeded", |req, ctx| wrap(req, ctx, get_seeded))
         .get_async("/list-keys", |req, ctx| wrap(req, ctx, list_keys))
         .get_async("/put-simple", |req, ctx| wrap(req, ctx, put_simple))
         .get_async("/put-metadata", |req, ctx| wrap(req, ctx, put_metadata))
+        .get_async("/put-metadata-struct", |req, ctx| {
+            wrap(req, ctx, put_metadata_struct)
+        })
         .get_async("/put-expiration", |req, ctx| wrap(req, ctx, put_expiration))
+        .get_async("/sequence", |req, ctx| wrap(req, ctx, sequence))
+        .get_async("/diagnose", |req, ctx| wrap(req, ctx, diagnose))
+        .get_async("/json-patch", |req, ctx| wrap(req, ctx, json_patch))
         .run(req, env)
         .await
 }
@@ -51,6 +59,12 @@ async fn get(_: Request, ctx: RouteContext<KvStore>) -> TestResult {
         })
 }
 
+async fn get_seeded(_: Request, ctx: RouteContext<KvStore>) -> TestResult {
+    let value = ctx.data.get("seeded").text().await?;
+    kv_assert_eq!(value.as_deref(), Some("seeded value"))?;
+    Ok("passed".into())
+}
+
 async fn get_not_found(_: Request, ctx: RouteContext<KvStore>) -> TestResult {
     let store = ctx.data;
     let value = store.get("not_found").text().await;
@@ -92,6 +106,28 @@ async fn put_metadata(_: Request, ctx: RouteContext<KvStore>) -> TestResult {
     Ok("passed".into())
 }
 
+async fn put_metadata_struct(_: Request, ctx: RouteContext<KvStore>) -> TestResult {
+    let store = ctx.data;
+    let metadata = NamedMetadata {
+        id: 100,
+        tag: "test".into(),
+    };
+    store
+        .put("put_d", "test")?
+        .metadata(&metadata)?
+        .execute()
+        .await?;
+
+    let (val, meta) = store
+        .get("put_d")
+        .text_with_metadata::<NamedMetadata>()
+        .await?;
+    kv_assert_eq!(val.unwrap(), "test")?;
+    kv_assert_eq!(meta.unwrap(), metadata)?;
+
+    Ok("passed".into())
+}
+
 async fn put_expiration(_: Request, ctx: RouteContext<KvStore>) -> TestResult {
     const EXPIRATION: u64 = 2000000000;
     let store = ctx.data;
@@ -115,6 +151,50 @@ async fn put_expiration(_: Request, ctx: RouteContext<KvStore>) -> TestResult {
     Ok("passed".into())
 }
 
+async fn sequence(_: Request, ctx: RouteContext<KvStore>) -> TestResult {
+    let store = ctx.data;
+    let sequence = store.sequence::<u32>("sequence").max_len(2);
+    for item in [1, 2, 3] {
+        sequence.push(item).await?;
+    }
+
+    kv_assert_eq!(sequence.items().await?, vec![2, 3])?;
+    kv_assert_eq!(sequence.pop().await?, Some(3))?;
+    kv_assert_eq!(sequence.len().await?, 1)?;
+
+    Ok("passed".into())
+}
+
+async fn diagnose(_: Request, ctx: RouteContext<KvStore>) -> TestResult {
+    let store = ctx.data;
+    let diagnostics = store.diagnose().await?;
+
+    kv_assert_eq!(diagnostics.errors, Vec::<String>::new())?;
+    kv_assert_eq!(diagnostics.is_healthy(), true)?;
+
+    Ok("passed".into())
+}
+
+async fn json_patch(_: Request, ctx: RouteContext<KvStore>) -> TestResult {
+    let store = ctx.data;
+    store
+        .put("json_patch", r#"{"a":1,"b":{"c":2}}"#)?
+        .execute()
+        .await?;
+    store
+        .put_json_patch(
+            "json_patch",
+            &serde_json::json!({ "b": { "c": null, "d": 3 }, "e": 4 }),
+        )
+        .await?;
+
+    let val = store.get("json_patch").text().await?.unwrap();
+    let val: serde_json::Value = serde_json::from_str(&val).map_err(KvError::from)?;
+    kv_assert_eq!(val, serde_json::json!({ "a": 1, "b": { "d": 3 }, "e": 4 }))?;
+
+    Ok("passed".into())
+}
+
 async fn wrap<T>(
     req: Request,
     ctx: RouteContext<KvStore>,
@@ -126,11 +206,18 @@ where
     let result = func(req, ctx);
 
     match result.await {
-        Ok(value) => Response::ok(value),
-        Err(e) => Response::ok(e.to_string()).map(|res| res.with_status(500)),
+        Ok(value) => Response::from_json(&serde_json::json!({ "success": value })),
+        Err(e) => Response::from_json(&serde_json::json!({ "failure": e.to_string() }))
+            .map(|res| res.with_status(500)),
     }
 }
 
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct NamedMetadata {
+    id: u32,
+    tag: String,
+}
+
 #[derive(Debug, thiserror::Error)]
 enum TestError {
     #[error("{0}")]